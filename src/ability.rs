@@ -0,0 +1,941 @@
+use std::io;
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::math::vec3;
+use bevy::prelude::*;
+use bevy::time::Stopwatch;
+use bevy_egui::{egui, EguiContexts};
+use kdtree::distance::squared_euclidean;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::AnimationPlayer;
+use crate::bindings::{Action, ActionInput, ActionMap, Binding};
+use crate::collision::EnemyKdTree;
+use crate::combat::apply_resistance;
+use crate::components::{
+    AttackSpeed, CritChance, CritMultiplier, Damage, DamageType, DamageVariance, Health, Knockback,
+    NearestEnemy, Pierces, Range,
+};
+use crate::configs::*;
+use crate::enemy::{Enemy, EnemyType, LastDamageSource};
+use crate::events::{AbilityHit, DamageSource, ShotFired};
+use crate::gun::{fire_bullet, WeaponStats, WeaponStatsBundle};
+use crate::player::Player;
+use crate::prestige::GlobalMultipliers;
+use crate::projectile::ProjectileModifiers;
+use crate::resources::{GlobalTextureAtlas, LoadingAssets};
+use crate::state::GameState;
+use crate::status::{OnHitEffects, StatusEffects, StatusKind};
+use crate::timescale::{simulation_running, TimeScale};
+
+pub struct AbilityPlugin;
+
+/// The abilities every run starts with, read from `ABILITY_BOOK_PATH`.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AbilityBook {
+    pub abilities: Vec<Ability>,
+}
+
+#[derive(Debug, Clone, Deserialize, Reflect)]
+pub struct Ability {
+    pub name: String,
+    pub cooldown: f32,
+    /// Whether the slot starts out casting on its own.
+    #[serde(default)]
+    pub auto_cast: bool,
+    pub targeting: Targeting,
+    pub effects: Vec<AbilityEffect>,
+}
+
+/// The shape an ability covers. Every enemy inside it is affected.
+#[derive(Debug, Clone, Copy, Deserialize, Reflect)]
+pub enum Targeting {
+    Caster {
+        radius: f32,
+    },
+    /// Aims at the nearest enemy in range, or the way the caster faces. `angle` is in degrees.
+    Cone {
+        range: f32,
+        angle: f32,
+    },
+    /// Can't be cast without an enemy in range.
+    NearestEnemy {
+        range: f32,
+        radius: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Reflect)]
+pub enum AbilityEffect {
+    Damage {
+        amount: f32,
+        damage_type: DamageType,
+    },
+    Status {
+        kind: StatusKind,
+        magnitude: f32,
+        duration: f32,
+    },
+    /// A turret at the centre of the shape that shoots the nearest enemy until it expires.
+    Summon {
+        duration: f32,
+        damage: f32,
+        damage_type: DamageType,
+        attack_interval: f32,
+        range: f32,
+    },
+}
+
+/// The player's ability bar.
+#[derive(Component, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Abilities {
+    pub slots: Vec<AbilitySlot>,
+}
+
+#[derive(Clone, Reflect)]
+pub struct AbilitySlot {
+    pub ability: Ability,
+    /// Seconds until the ability can be cast again.
+    pub remaining: f32,
+    pub auto_cast: bool,
+}
+
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Summon {
+    /// Seconds left before the summon disappears.
+    pub remaining: f32,
+}
+
+/// Asks to cast the ability in a slot of the player's ability bar.
+#[derive(Event)]
+pub struct CastAbility {
+    pub slot: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct AbilityBookHandle(pub Handle<AbilityBook>);
+
+#[derive(Default)]
+pub struct AbilityBookLoader;
+
+#[derive(Debug)]
+pub enum AbilityBookError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+/// The centre of an ability's shape and the enemies inside it.
+struct AbilityTarget {
+    center: Vec2,
+    enemies: Vec<Entity>,
+}
+
+impl std::fmt::Display for AbilityBookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbilityBookError::Io(error) => write!(f, "{}", error),
+            AbilityBookError::Parse(error) => write!(f, "{}", error),
+            AbilityBookError::Invalid(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for AbilityBookError {}
+
+impl AssetLoader for AbilityBookLoader {
+    type Asset = AbilityBook;
+    type Settings = ();
+    type Error = AbilityBookError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(AbilityBookError::Io)?;
+        let book: AbilityBook = ron::de::from_bytes(&bytes).map_err(AbilityBookError::Parse)?;
+        book.validate().map_err(AbilityBookError::Invalid)?;
+        Ok(book)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["abilities.ron"]
+    }
+}
+
+impl AbilityBook {
+    /// Checks the book for values the game can't use, reporting the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.abilities.len() > ABILITY_SLOTS {
+            return Err(format!(
+                "{} abilities don't fit in {} slots",
+                self.abilities.len(),
+                ABILITY_SLOTS
+            ));
+        }
+
+        for ability in self.abilities.iter() {
+            check_amount(ability, "cooldown", ability.cooldown)?;
+            if ability.effects.is_empty() {
+                return Err(format!("ability \"{}\" has no effects", ability.name));
+            }
+            match ability.targeting {
+                Targeting::Caster { radius } => check_amount(ability, "radius", radius)?,
+                Targeting::Cone { range, angle } => {
+                    check_amount(ability, "range", range)?;
+                    // Written this way round so a NaN angle fails too
+                    if !(angle > 0.0 && angle <= 360.0) {
+                        return Err(format!(
+                            "ability \"{}\" has a cone of {} degrees",
+                            ability.name, angle
+                        ));
+                    }
+                }
+                Targeting::NearestEnemy { range, radius } => {
+                    check_amount(ability, "range", range)?;
+                    check_amount(ability, "radius", radius)?;
+                }
+            }
+            for effect in ability.effects.iter() {
+                match *effect {
+                    AbilityEffect::Damage { amount, .. } => {
+                        check_amount(ability, "damage", amount)?;
+                    }
+                    AbilityEffect::Status {
+                        magnitude,
+                        duration,
+                        ..
+                    } => {
+                        check_amount(ability, "status magnitude", magnitude)?;
+                        check_amount(ability, "status duration", duration)?;
+                    }
+                    AbilityEffect::Summon {
+                        duration,
+                        damage,
+                        attack_interval,
+                        range,
+                        ..
+                    } => {
+                        check_amount(ability, "summon duration", duration)?;
+                        check_amount(ability, "summon damage", damage)?;
+                        check_amount(ability, "summon range", range)?;
+                        if !(attack_interval > 0.0 && attack_interval.is_finite()) {
+                            return Err(format!(
+                                "ability \"{}\" summons a turret that never fires",
+                                ability.name
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects a negative or non-finite number in an ability.
+fn check_amount(ability: &Ability, what: &str, value: f32) -> Result<(), String> {
+    if value.is_finite() && value >= 0.0 {
+        return Ok(());
+    }
+    Err(format!(
+        "ability \"{}\" has a {} of {}",
+        ability.name, what, value
+    ))
+}
+
+impl Abilities {
+    pub fn from_book(book: &AbilityBook) -> Self {
+        Self {
+            slots: book
+                .abilities
+                .iter()
+                .map(|ability| AbilitySlot {
+                    ability: ability.clone(),
+                    remaining: 0.0,
+                    auto_cast: ability.auto_cast,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl AbilitySlot {
+    pub fn is_ready(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    /// How far the cooldown has come back, from 0 right after a cast to 1 when ready.
+    pub fn progress(&self) -> f32 {
+        if self.ability.cooldown <= 0.0 {
+            return 1.0;
+        }
+        (1.0 - self.remaining / self.ability.cooldown).clamp(0.0, 1.0)
+    }
+}
+
+impl Targeting {
+    /// Finds what a cast from `caster` would hit, or `None` if it has nothing to aim at.
+    fn resolve(
+        &self,
+        caster: Vec2,
+        facing: Vec2,
+        nearest_enemy: &NearestEnemy,
+        enemy_kdtree: &EnemyKdTree,
+        enemy_position: impl Fn(Entity) -> Option<Vec2>,
+    ) -> Option<AbilityTarget> {
+        let nearest_within = |range: f32| {
+            nearest_enemy
+                .entity
+                .filter(|_| nearest_enemy.distance <= range)
+                .and_then(|enemy| Some((enemy, enemy_position(enemy)?)))
+        };
+        let within = |center: Vec2, radius: f32| -> Vec<Entity> {
+            enemy_kdtree
+                .tree
+                .within(&[center.x, center.y], radius * radius, &squared_euclidean)
+                .map(|found| found.into_iter().map(|(_, &enemy)| enemy).collect())
+                .unwrap_or_default()
+        };
+
+        match *self {
+            Targeting::Caster { radius } => Some(AbilityTarget {
+                center: caster,
+                enemies: within(caster, radius),
+            }),
+            Targeting::Cone { range, angle } => {
+                let aim = nearest_within(range)
+                    .and_then(|(_, position)| (position - caster).try_normalize())
+                    .unwrap_or(facing);
+                let half_angle = (angle / 2.0).to_radians();
+                let enemies = within(caster, range)
+                    .into_iter()
+                    .filter(|&enemy| {
+                        enemy_position(enemy).is_some_and(|position| {
+                            let offset = position - caster;
+                            offset == Vec2::ZERO || aim.angle_between(offset).abs() <= half_angle
+                        })
+                    })
+                    .collect();
+                Some(AbilityTarget {
+                    center: caster,
+                    enemies,
+                })
+            }
+            Targeting::NearestEnemy { range, radius } => {
+                let (target, center) = nearest_within(range)?;
+                let mut enemies = within(center, radius);
+                if !enemies.contains(&target) {
+                    enemies.push(target);
+                }
+                Some(AbilityTarget { center, enemies })
+            }
+        }
+    }
+}
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AbilityBook>()
+            .init_asset_loader::<AbilityBookLoader>()
+            .init_resource::<AbilityBookHandle>()
+            .add_event::<CastAbility>()
+            .add_systems(OnEnter(GameState::Loading), load_ability_book)
+            .add_systems(
+                Update,
+                (
+                    equip_abilities,
+                    // Casts are only read while the simulation runs, so don't queue any
+                    read_ability_keys.run_if(simulation_running),
+                    ability_bar,
+                )
+                    .chain()
+                    .before(cast_abilities)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (cast_abilities, update_summons)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(simulation_running),
+            );
+    }
+}
+
+fn load_ability_book(
+    mut book: ResMut<AbilityBookHandle>,
+    mut loading: ResMut<LoadingAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    book.0 = asset_server.load(ABILITY_BOOK_PATH);
+    loading.handles.push(book.0.clone().untyped());
+}
+
+/// Fills the ability bar of a new player, including one loaded from a scene.
+fn equip_abilities(
+    mut commands: Commands,
+    book: Res<AbilityBookHandle>,
+    books: Res<Assets<AbilityBook>>,
+    player_query: Query<Entity, (With<Player>, Without<Abilities>)>,
+) {
+    let Some(book) = books.get(&book.0) else {
+        return;
+    };
+    for player in player_query.iter() {
+        commands.entity(player).insert(Abilities::from_book(book));
+    }
+}
+
+fn read_ability_keys(
+    mut contexts: EguiContexts,
+    actions: ActionInput,
+    mut cast_events: EventWriter<CastAbility>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    for (slot, action) in Action::ABILITIES.into_iter().enumerate() {
+        if actions.just_pressed(action) {
+            cast_events.send(CastAbility { slot });
+        }
+    }
+}
+
+/// Shows each ability's cooldown. Clicking casts it, the checkbox switches auto-cast.
+fn ability_bar(
+    mut contexts: EguiContexts,
+    map: Res<ActionMap>,
+    time_scale: Res<TimeScale>,
+    mut player_query: Query<&mut Abilities, With<Player>>,
+    mut cast_events: EventWriter<CastAbility>,
+) {
+    let Ok(mut abilities) = player_query.get_single_mut() else {
+        return;
+    };
+    if abilities.slots.is_empty() {
+        return;
+    }
+
+    egui::Area::new(egui::Id::new("ability_bar"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -HUD_MARGIN])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for (slot, ability_slot) in abilities.slots.iter_mut().enumerate() {
+                        let key = Action::ABILITIES
+                            .get(slot)
+                            .and_then(|&action| map.get(action).first())
+                            .map(Binding::name)
+                            .unwrap_or_default();
+
+                        ui.vertical(|ui| {
+                            ui.set_width(ABILITY_BAR_SLOT_WIDTH);
+                            let label = format!("{} [{}]", ability_slot.ability.name, key);
+                            if ui
+                                .add_enabled(
+                                    ability_slot.is_ready() && time_scale.running(),
+                                    egui::Button::new(label),
+                                )
+                                .clicked()
+                            {
+                                cast_events.send(CastAbility { slot });
+                            }
+
+                            let status = if ability_slot.is_ready() {
+                                "Ready".to_string()
+                            } else {
+                                format!("{:.1}s", ability_slot.remaining)
+                            };
+                            ui.add(
+                                egui::ProgressBar::new(ability_slot.progress())
+                                    .desired_width(ABILITY_BAR_SLOT_WIDTH)
+                                    .text(status),
+                            );
+                            ui.checkbox(&mut ability_slot.auto_cast, "Auto");
+                        });
+                    }
+                });
+            });
+        });
+}
+
+/// Casts abilities asked for by key or click, and auto-cast ones as soon as they are
+/// ready and have an enemy to hit.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn cast_abilities(
+    mut commands: Commands,
+    time: Res<Time>,
+    handle: Res<GlobalTextureAtlas>,
+    enemy_kdtree: Res<EnemyKdTree>,
+    mut cast_events: EventReader<CastAbility>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Sprite,
+            &NearestEnemy,
+            &GlobalMultipliers,
+            &mut Abilities,
+        ),
+        (With<Player>, Without<Enemy>),
+    >,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &EnemyType,
+            &mut Health,
+            &mut StatusEffects,
+            &mut LastDamageSource,
+        ),
+        (With<Enemy>, Without<Player>),
+    >,
+    mut hit_events: EventWriter<AbilityHit>,
+) {
+    let requested: Vec<usize> = cast_events.read().map(|cast| cast.slot).collect();
+    let Ok((player, transform, sprite, nearest_enemy, multipliers, mut abilities)) =
+        player_query.get_single_mut()
+    else {
+        return;
+    };
+
+    let caster = transform.translation.truncate();
+    let facing = if sprite.flip_x { Vec2::NEG_X } else { Vec2::X };
+    for (slot, ability_slot) in abilities.slots.iter_mut().enumerate() {
+        ability_slot.remaining = (ability_slot.remaining - time.delta_seconds()).max(0.0);
+        if !ability_slot.is_ready() {
+            continue;
+        }
+
+        let manual = requested.contains(&slot);
+        if !manual && !ability_slot.auto_cast {
+            continue;
+        }
+        let Some(target) = ability_slot.ability.targeting.resolve(
+            caster,
+            facing,
+            nearest_enemy,
+            &enemy_kdtree,
+            |enemy| {
+                enemy_query
+                    .get(enemy)
+                    .ok()
+                    .map(|(transform, ..)| transform.translation.truncate())
+            },
+        ) else {
+            continue;
+        };
+        // Auto-cast waits for something to hit rather than firing into empty space
+        if !manual && target.enemies.is_empty() {
+            continue;
+        }
+
+        for effect in ability_slot.ability.effects.iter() {
+            match *effect {
+                AbilityEffect::Damage {
+                    amount,
+                    damage_type,
+                } => {
+                    for &enemy in target.enemies.iter() {
+                        let Ok((transform, enemy_type, mut health, _, mut last_source)) =
+                            enemy_query.get_mut(enemy)
+                        else {
+                            continue;
+                        };
+                        let damage = apply_resistance(
+                            amount * multipliers.damage,
+                            enemy_type.resistance(damage_type),
+                        );
+                        health.value -= damage;
+                        last_source.value = Some(DamageSource::Ability(player));
+                        hit_events.send(AbilityHit {
+                            caster: player,
+                            enemy,
+                            position: transform.translation.truncate(),
+                            damage,
+                            damage_type,
+                        });
+                    }
+                }
+                AbilityEffect::Status {
+                    kind,
+                    magnitude,
+                    duration,
+                } => {
+                    for &enemy in target.enemies.iter() {
+                        if let Ok((_, _, _, mut status, _)) = enemy_query.get_mut(enemy) {
+                            status.apply(kind, magnitude, duration);
+                        }
+                    }
+                }
+                AbilityEffect::Summon {
+                    duration,
+                    damage,
+                    damage_type,
+                    attack_interval,
+                    range,
+                } => {
+                    spawn_summon(
+                        &mut commands,
+                        &handle,
+                        target.center,
+                        Summon {
+                            remaining: duration,
+                        },
+                        attack_interval,
+                        WeaponStatsBundle {
+                            range: Range { value: range },
+                            damage: Damage {
+                                value: damage * multipliers.damage,
+                            },
+                            pierces: Pierces {
+                                value: BULLET_PIERCES,
+                            },
+                            crit_chance: CritChance { value: CRIT_CHANCE },
+                            crit_multiplier: CritMultiplier {
+                                value: CRIT_MULTIPLIER,
+                            },
+                            damage_variance: DamageVariance {
+                                value: DAMAGE_VARIANCE,
+                            },
+                            damage_type,
+                            knockback: Knockback {
+                                value: BULLET_KNOCKBACK,
+                            },
+                            on_hit: OnHitEffects::default().with_damage_type(damage_type),
+                            modifiers: ProjectileModifiers::default(),
+                        },
+                    );
+                }
+            }
+        }
+        ability_slot.remaining = ability_slot.ability.cooldown;
+    }
+}
+
+pub fn spawn_summon(
+    commands: &mut Commands,
+    handle: &GlobalTextureAtlas,
+    position: Vec2,
+    summon: Summon,
+    attack_interval: f32,
+    stats: WeaponStatsBundle,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: handle.image.clone(),
+                transform: Transform {
+                    translation: vec3(position.x, position.y, 15.0),
+                    scale: Vec3::splat(SPRITE_SCALE_FACTOR),
+                    ..default()
+                },
+                ..default()
+            },
+            handle.texture_atlas(SPRITE_GUN),
+            AnimationPlayer::new(SPRITE_GUN),
+            summon,
+            AttackSpeed {
+                timer: Stopwatch::new(),
+                interval: attack_interval,
+            },
+            stats,
+        ))
+        .id()
+}
+
+/// Turns summons toward the nearest enemy in range and fires at it, until they expire.
+fn update_summons(
+    mut commands: Commands,
+    time: Res<Time>,
+    handle: Res<GlobalTextureAtlas>,
+    enemy_kdtree: Res<EnemyKdTree>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Summon>)>,
+    mut summon_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Summon,
+            &mut AttackSpeed,
+            WeaponStats,
+        ),
+        Without<Enemy>,
+    >,
+    mut shot_events: EventWriter<ShotFired>,
+) {
+    for (entity, mut transform, mut summon, mut attack_speed, stats) in summon_query.iter_mut() {
+        summon.remaining -= time.delta_seconds();
+        if summon.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        attack_speed.timer.tick(time.delta());
+        let position = transform.translation.truncate();
+        let Some(target) = enemy_kdtree
+            .tree
+            .nearest(&[position.x, position.y], 1, &squared_euclidean)
+            .ok()
+            .and_then(|nearest| nearest.first().copied())
+            .filter(|(distance, _)| distance.sqrt() <= stats.range.value)
+            .and_then(|(_, &enemy)| enemy_query.get(enemy).ok())
+        else {
+            continue;
+        };
+
+        let Some(direction) = (target.translation.truncate() - position).try_normalize() else {
+            continue;
+        };
+        transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+
+        if attack_speed.timer.elapsed_secs() >= attack_speed.interval {
+            attack_speed.timer.reset();
+            let bullet = fire_bullet(
+                &mut commands,
+                &handle,
+                position,
+                direction.extend(0.0),
+                stats.to_bundle(),
+            );
+            shot_events.send(ShotFired {
+                gun: entity,
+                bullet,
+                position,
+                direction,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::vec2;
+
+    fn ability(targeting: Targeting, effects: Vec<AbilityEffect>) -> Ability {
+        Ability {
+            name: "test".to_string(),
+            cooldown: 1.0,
+            auto_cast: false,
+            targeting,
+            effects,
+        }
+    }
+
+    fn damage() -> AbilityEffect {
+        AbilityEffect::Damage {
+            amount: 10.0,
+            damage_type: DamageType::Physical,
+        }
+    }
+
+    fn book(ability: Ability) -> AbilityBook {
+        AbilityBook {
+            abilities: vec![ability],
+        }
+    }
+
+    fn cone(angle: f32) -> Ability {
+        ability(
+            Targeting::Cone {
+                range: 100.0,
+                angle,
+            },
+            vec![damage()],
+        )
+    }
+
+    /// Enemies at the given positions, with the first one as the nearest.
+    fn arena(positions: &[Vec2]) -> (EnemyKdTree, NearestEnemy, Vec<Entity>) {
+        let mut tree = EnemyKdTree::default();
+        let enemies: Vec<Entity> = (0..positions.len() as u32).map(Entity::from_raw).collect();
+        for (position, &enemy) in positions.iter().zip(enemies.iter()) {
+            tree.tree.add([position.x, position.y], enemy).unwrap();
+        }
+        let nearest = NearestEnemy {
+            entity: enemies.first().copied(),
+            distance: positions
+                .first()
+                .map_or(f32::MAX, |position| position.length()),
+        };
+        (tree, nearest, enemies)
+    }
+
+    fn resolve(targeting: Targeting, positions: &[Vec2]) -> Option<(Vec2, Vec<Entity>)> {
+        let (tree, nearest, enemies) = arena(positions);
+        let position = |enemy: Entity| {
+            enemies
+                .iter()
+                .position(|&other| other == enemy)
+                .map(|index| positions[index])
+        };
+        targeting
+            .resolve(Vec2::ZERO, Vec2::X, &nearest, &tree, position)
+            .map(|target| (target.center, target.enemies))
+    }
+
+    #[test]
+    fn shipped_book_is_valid() {
+        let book: AbilityBook =
+            ron::de::from_str(include_str!("../assets/player.abilities.ron")).unwrap();
+        assert_eq!(book.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_too_many_abilities() {
+        let abilities = vec![cone(90.0); ABILITY_SLOTS + 1];
+        assert!(AbilityBook { abilities }.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_an_ability_without_effects() {
+        let ability = ability(Targeting::Caster { radius: 10.0 }, Vec::new());
+        assert!(book(ability).validate().is_err());
+    }
+
+    #[test]
+    fn rejects_bad_cooldowns() {
+        for cooldown in [-1.0, f32::NAN, f32::INFINITY] {
+            let mut ability = cone(90.0);
+            ability.cooldown = cooldown;
+            assert!(book(ability).validate().is_err(), "{}", cooldown);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_shapes() {
+        for value in [-1.0, f32::NAN, f32::INFINITY] {
+            let shapes = [
+                Targeting::Caster { radius: value },
+                Targeting::Cone {
+                    range: value,
+                    angle: 90.0,
+                },
+                Targeting::NearestEnemy {
+                    range: value,
+                    radius: 10.0,
+                },
+                Targeting::NearestEnemy {
+                    range: 100.0,
+                    radius: value,
+                },
+            ];
+            for targeting in shapes {
+                let ability = ability(targeting, vec![damage()]);
+                assert!(book(ability).validate().is_err(), "{:?}", targeting);
+            }
+        }
+    }
+
+    #[test]
+    fn cone_angle_limits() {
+        assert!(book(cone(0.0)).validate().is_err());
+        assert!(book(cone(-45.0)).validate().is_err());
+        assert!(book(cone(360.5)).validate().is_err());
+        assert!(book(cone(f32::NAN)).validate().is_err());
+        assert!(book(cone(0.1)).validate().is_ok());
+        assert!(book(cone(360.0)).validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_effect_numbers() {
+        let effects = [
+            AbilityEffect::Status {
+                kind: StatusKind::Burn,
+                magnitude: 5.0,
+                duration: -1.0,
+            },
+            AbilityEffect::Summon {
+                duration: f32::NAN,
+                damage: 5.0,
+                damage_type: DamageType::Fire,
+                attack_interval: 1.0,
+                range: 100.0,
+            },
+            AbilityEffect::Summon {
+                duration: 5.0,
+                damage: 5.0,
+                damage_type: DamageType::Fire,
+                attack_interval: 0.0,
+                range: 100.0,
+            },
+        ];
+        for effect in effects {
+            let ability = ability(Targeting::Caster { radius: 10.0 }, vec![effect]);
+            assert!(book(ability).validate().is_err(), "{:?}", effect);
+        }
+    }
+
+    #[test]
+    fn caster_hits_everything_in_radius() {
+        let positions = [vec2(10.0, 0.0), vec2(0.0, -20.0), vec2(100.0, 0.0)];
+        let (center, enemies) = resolve(Targeting::Caster { radius: 50.0 }, &positions).unwrap();
+
+        assert_eq!(center, Vec2::ZERO);
+        assert_eq!(enemies.len(), 2);
+        assert!(!enemies.contains(&Entity::from_raw(2)));
+    }
+
+    #[test]
+    fn cone_aims_at_the_nearest_enemy() {
+        // The nearest enemy is straight up, so the one to the right is outside a 90 degree cone
+        let positions = [vec2(0.0, 10.0), vec2(30.0, 0.0), vec2(5.0, 40.0)];
+        let targeting = Targeting::Cone {
+            range: 50.0,
+            angle: 90.0,
+        };
+        let (_, enemies) = resolve(targeting, &positions).unwrap();
+
+        assert_eq!(enemies.len(), 2);
+        assert!(!enemies.contains(&Entity::from_raw(1)));
+    }
+
+    #[test]
+    fn cone_falls_back_to_facing() {
+        let positions = [vec2(200.0, 0.0), vec2(30.0, 0.0), vec2(-30.0, 0.0)];
+        let targeting = Targeting::Cone {
+            range: 50.0,
+            angle: 60.0,
+        };
+        let (_, enemies) = resolve(targeting, &positions).unwrap();
+
+        assert_eq!(enemies, vec![Entity::from_raw(1)]);
+    }
+
+    #[test]
+    fn full_cone_hits_all_around() {
+        let positions = [vec2(10.0, 0.0), vec2(-30.0, 0.0), vec2(0.0, -30.0)];
+        let targeting = Targeting::Cone {
+            range: 50.0,
+            angle: 360.0,
+        };
+        let (_, enemies) = resolve(targeting, &positions).unwrap();
+
+        assert_eq!(enemies.len(), 3);
+    }
+
+    #[test]
+    fn nearest_enemy_needs_a_target_in_range() {
+        let targeting = Targeting::NearestEnemy {
+            range: 50.0,
+            radius: 20.0,
+        };
+        assert!(resolve(targeting, &[]).is_none());
+        assert!(resolve(targeting, &[vec2(100.0, 0.0)]).is_none());
+
+        let positions = [vec2(40.0, 0.0), vec2(50.0, 10.0), vec2(0.0, 0.0)];
+        let (center, enemies) = resolve(targeting, &positions).unwrap();
+        assert_eq!(center, vec2(40.0, 0.0));
+        assert_eq!(enemies.len(), 2);
+        assert!(!enemies.contains(&Entity::from_raw(2)));
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+
+use crate::configs::*;
+use crate::events::{AbilityHit, BulletHit, EnemyKilled, PlayerDamaged, PlayerKilled, ShotFired};
+use crate::resources::GlobalTextureAtlas;
+use crate::state::GameState;
+use crate::timescale::simulation_running;
+
+pub struct AnimationPlugin;
+
+/// Named clips looked up in the atlas manifest as `<sprite>_<clip>`, e.g. `player_walk`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationClip {
+    Idle,
+    Walk,
+    Hurt,
+    Death,
+    Fire,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationMode {
+    Loop,
+    /// Plays once and holds the last frame. Movement can't interrupt it.
+    Once,
+}
+
+/// Steps a `TextureAtlas` through the frames of the current clip.
+#[derive(Component)]
+pub struct AnimationPlayer {
+    pub sprite: &'static str,
+    pub clip: AnimationClip,
+    pub mode: AnimationMode,
+    pub frame: usize,
+    pub timer: Timer,
+    pub finished: bool,
+    /// Set when the clip changes so the first frame is shown right away.
+    restart: bool,
+    /// Where the entity was last frame, used to tell walking from standing.
+    last_position: Option<Vec2>,
+}
+
+/// Removes the entity once its one-shot clip has finished, e.g. a death animation.
+#[derive(Component)]
+pub struct DespawnOnFinish;
+
+impl AnimationClip {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnimationClip::Idle => "idle",
+            AnimationClip::Walk => "walk",
+            AnimationClip::Hurt => "hurt",
+            AnimationClip::Death => "death",
+            AnimationClip::Fire => "fire",
+        }
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new(sprite: &'static str) -> Self {
+        Self {
+            sprite,
+            clip: AnimationClip::Idle,
+            mode: AnimationMode::Loop,
+            frame: 0,
+            timer: Timer::default(),
+            finished: false,
+            restart: true,
+            last_position: None,
+        }
+    }
+
+    /// Switches clip. Replaying the clip that is already looping does nothing.
+    pub fn play(&mut self, clip: AnimationClip, mode: AnimationMode) {
+        if clip == self.clip && mode == AnimationMode::Loop && self.mode == AnimationMode::Loop {
+            return;
+        }
+        self.clip = clip;
+        self.mode = mode;
+        self.restart = true;
+    }
+
+    /// Plays a looping clip unless a one-shot clip is still running.
+    pub fn play_looping(&mut self, clip: AnimationClip) {
+        if self.mode == AnimationMode::Once && !self.finished {
+            return;
+        }
+        self.play(clip, AnimationMode::Loop);
+    }
+
+    pub fn clip_name(&self) -> String {
+        format!("{}_{}", self.sprite, self.clip.name())
+    }
+}
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                animate_movement,
+                animate_combat,
+                spawn_corpses,
+                advance_animations,
+                despawn_finished_animations,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
+        );
+    }
+}
+
+fn animate_movement(mut query: Query<(&Transform, &mut AnimationPlayer)>) {
+    for (transform, mut player) in query.iter_mut() {
+        let position = transform.translation.truncate();
+        let moved = player
+            .last_position
+            .is_some_and(|last| last.distance(position) > ANIMATION_WALK_THRESHOLD);
+        player.last_position = Some(position);
+
+        if player.clip == AnimationClip::Death {
+            continue;
+        }
+        let clip = if moved {
+            AnimationClip::Walk
+        } else {
+            AnimationClip::Idle
+        };
+        player.play_looping(clip);
+    }
+}
+
+fn animate_combat(
+    mut query: Query<&mut AnimationPlayer>,
+    mut hit_events: EventReader<BulletHit>,
+    mut ability_events: EventReader<AbilityHit>,
+    mut damaged_events: EventReader<PlayerDamaged>,
+    mut shot_events: EventReader<ShotFired>,
+) {
+    let hurt = hit_events
+        .read()
+        .map(|hit| hit.enemy)
+        .chain(ability_events.read().map(|hit| hit.enemy))
+        .chain(damaged_events.read().map(|damaged| damaged.player));
+    for entity in hurt {
+        if let Ok(mut player) = query.get_mut(entity) {
+            player.play(AnimationClip::Hurt, AnimationMode::Once);
+        }
+    }
+
+    for shot in shot_events.read() {
+        if let Ok(mut player) = query.get_mut(shot.gun) {
+            player.play(AnimationClip::Fire, AnimationMode::Once);
+        }
+    }
+}
+
+/// Leaves a body behind that plays the death clip, since the entity itself is already gone.
+fn spawn_corpses(
+    mut commands: Commands,
+    handle: Res<GlobalTextureAtlas>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut player_killed_events: EventReader<PlayerKilled>,
+) {
+    let corpses = enemy_killed_events
+        .read()
+        .map(|killed| (killed.enemy_type.sprite_name(), killed.position))
+        .chain(
+            player_killed_events
+                .read()
+                .map(|killed| (SPRITE_PLAYER, killed.position)),
+        );
+
+    for (sprite, position) in corpses {
+        let mut player = AnimationPlayer::new(sprite);
+        player.play(AnimationClip::Death, AnimationMode::Once);
+        commands.spawn((
+            SpriteBundle {
+                texture: handle.image.clone(),
+                transform: Transform {
+                    translation: position.extend(0.5),
+                    scale: Vec3::splat(SPRITE_SCALE_FACTOR),
+                    ..default()
+                },
+                ..default()
+            },
+            handle.texture_atlas(sprite),
+            player,
+            DespawnOnFinish,
+        ));
+    }
+}
+
+fn advance_animations(
+    time: Res<Time>,
+    handle: Res<GlobalTextureAtlas>,
+    mut query: Query<(&mut AnimationPlayer, &mut TextureAtlas)>,
+) {
+    for (mut player, mut atlas) in query.iter_mut() {
+        if player.restart {
+            player.restart = false;
+            player.frame = 0;
+            player.finished = false;
+            let Some(animation) = handle.animation(&player.clip_name()) else {
+                // No strip for this clip: show the static sprite and let it end at once
+                atlas.index = handle.sprite(player.sprite);
+                player.finished = true;
+                continue;
+            };
+            player.timer = Timer::from_seconds(1.0 / animation.fps, TimerMode::Repeating);
+            atlas.index = animation.frames[0];
+            continue;
+        }
+
+        if player.finished || !player.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(animation) = handle.animation(&player.clip_name()) else {
+            continue;
+        };
+
+        let next = player.frame + 1;
+        if next < animation.frames.len() {
+            player.frame = next;
+        } else if player.mode == AnimationMode::Loop {
+            player.frame = 0;
+        } else {
+            player.finished = true;
+        }
+        atlas.index = animation.frames[player.frame];
+    }
+}
+
+fn despawn_finished_animations(
+    mut commands: Commands,
+    query: Query<(Entity, &AnimationPlayer), With<DespawnOnFinish>>,
+) {
+    for (entity, player) in query.iter() {
+        if player.finished {
+            commands.entity(entity).despawn();
+        }
+    }
+}
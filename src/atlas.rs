@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::io;
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::configs::REQUIRED_SPRITES;
+
+/// Names the frames of the sprite sheet so the code never hard-codes atlas indices.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AtlasManifest {
+    /// Sprite sheet path, relative to the assets folder.
+    pub image: String,
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    pub sprites: HashMap<String, usize>,
+    #[serde(default)]
+    pub animations: HashMap<String, SpriteAnimation>,
+    #[serde(skip)]
+    #[dependency]
+    pub texture: Handle<Image>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpriteAnimation {
+    pub frames: Vec<usize>,
+    pub fps: f32,
+}
+
+#[derive(Default)]
+pub struct AtlasManifestLoader;
+
+#[derive(Debug)]
+pub enum AtlasManifestError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for AtlasManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtlasManifestError::Io(error) => write!(f, "{}", error),
+            AtlasManifestError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for AtlasManifestError {}
+
+impl AssetLoader for AtlasManifestLoader {
+    type Asset = AtlasManifest;
+    type Settings = ();
+    type Error = AtlasManifestError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(AtlasManifestError::Io)?;
+        let mut manifest: AtlasManifest =
+            ron::de::from_bytes(&bytes).map_err(AtlasManifestError::Parse)?;
+        manifest.texture = load_context.load(manifest.image.clone());
+        Ok(manifest)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.ron"]
+    }
+}
+
+impl AtlasManifest {
+    pub fn frame_count(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    pub fn layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(self.tile_size, self.columns, self.rows, None, None)
+    }
+
+    /// Checks the manifest against the loaded sprite sheet, reporting the first problem found.
+    pub fn validate(&self, image_size: UVec2) -> Result<(), String> {
+        let expected = self.tile_size * UVec2::new(self.columns, self.rows);
+        if expected != image_size {
+            return Err(format!(
+                "{}x{} tiles of {}x{} need a {}x{} image, but {} is {}x{}",
+                self.columns,
+                self.rows,
+                self.tile_size.x,
+                self.tile_size.y,
+                expected.x,
+                expected.y,
+                self.image,
+                image_size.x,
+                image_size.y
+            ));
+        }
+
+        for name in REQUIRED_SPRITES {
+            if !self.sprites.contains_key(name) {
+                return Err(format!("missing sprite \"{}\"", name));
+            }
+        }
+
+        let frame_count = self.frame_count();
+        for (name, &index) in self.sprites.iter() {
+            if index >= frame_count {
+                return Err(format!(
+                    "sprite \"{}\" uses frame {} of {}",
+                    name, index, frame_count
+                ));
+            }
+        }
+
+        for (name, animation) in self.animations.iter() {
+            if animation.frames.is_empty() || animation.fps <= 0.0 {
+                return Err(format!("animation \"{}\" has no frames to play", name));
+            }
+            if let Some(index) = animation.frames.iter().find(|&&index| index >= frame_count) {
+                return Err(format!(
+                    "animation \"{}\" uses frame {} of {}",
+                    name, index, frame_count
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
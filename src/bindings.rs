@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::configs::ABILITY_SLOTS;
+use crate::state::GameState;
+
+pub struct BindingsPlugin;
+
+/// Something the player can do with a key or button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Pause,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Ability1,
+    Ability2,
+    Ability3,
+    Ability4,
+    ToggleShop,
+    ToggleConsole,
+    SpawnAtCursor,
+    TimeSlower,
+    TimeFaster,
+    TimeReset,
+    TimeFreeze,
+    TimeStep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Matches the button on any connected gamepad.
+    Gamepad(GamepadButtonType),
+}
+
+/// The keys and buttons bound to each action. Stored with the profile settings.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+/// The action waiting for a key press on the bindings screen, and which of its bindings
+/// the press replaces. A slot past the end adds a new binding.
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    pub action: Option<Action>,
+    pub slot: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct BindingsOpen(pub bool);
+
+/// Reads actions through the action map instead of raw keys.
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    map: Res<'w, ActionMap>,
+    rebinding: Res<'w, Rebinding>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::Pause,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Dash,
+        Action::Ability1,
+        Action::Ability2,
+        Action::Ability3,
+        Action::Ability4,
+        Action::ToggleShop,
+        Action::ToggleConsole,
+        Action::SpawnAtCursor,
+        Action::TimeSlower,
+        Action::TimeFaster,
+        Action::TimeReset,
+        Action::TimeFreeze,
+        Action::TimeStep,
+    ];
+
+    /// Casts the ability in the matching slot of the ability bar.
+    pub const ABILITIES: [Action; ABILITY_SLOTS] = [
+        Action::Ability1,
+        Action::Ability2,
+        Action::Ability3,
+        Action::Ability4,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Pause => "Pause / back",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Dash => "Dash",
+            Action::Ability1 => "Ability 1",
+            Action::Ability2 => "Ability 2",
+            Action::Ability3 => "Ability 3",
+            Action::Ability4 => "Ability 4",
+            Action::ToggleShop => "Shop",
+            Action::ToggleConsole => "Debug console",
+            Action::SpawnAtCursor => "Spawn enemy at cursor",
+            Action::TimeSlower => "Slow down time",
+            Action::TimeFaster => "Speed up time",
+            Action::TimeReset => "Normal speed",
+            Action::TimeFreeze => "Freeze time",
+            Action::TimeStep => "Step one tick",
+        }
+    }
+
+    fn default_bindings(&self) -> Vec<Binding> {
+        use Binding::*;
+        match self {
+            Action::Pause => vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)],
+            Action::MoveUp => vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)],
+            Action::MoveDown => vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            Action::MoveLeft => vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            Action::MoveRight => vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            Action::Dash => vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::South)],
+            Action::Ability1 => vec![Key(KeyCode::Digit1), Gamepad(GamepadButtonType::West)],
+            Action::Ability2 => vec![Key(KeyCode::Digit2), Gamepad(GamepadButtonType::North)],
+            Action::Ability3 => vec![Key(KeyCode::Digit3), Gamepad(GamepadButtonType::East)],
+            Action::Ability4 => vec![
+                Key(KeyCode::Digit4),
+                Gamepad(GamepadButtonType::RightTrigger),
+            ],
+            Action::ToggleShop => vec![Key(KeyCode::KeyB)],
+            Action::ToggleConsole => vec![Key(KeyCode::F1)],
+            Action::SpawnAtCursor => vec![Key(KeyCode::F2)],
+            Action::TimeSlower => vec![Key(KeyCode::BracketLeft)],
+            Action::TimeFaster => vec![Key(KeyCode::BracketRight)],
+            Action::TimeReset => vec![Key(KeyCode::Backslash)],
+            Action::TimeFreeze => vec![Key(KeyCode::KeyP)],
+            Action::TimeStep => vec![Key(KeyCode::Period)],
+        }
+    }
+}
+
+impl Binding {
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{:?}", key);
+                match name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                {
+                    Some(short) => short.to_string(),
+                    None => name,
+                }
+            }
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    /// Gives actions missing from a saved map their default bindings.
+    pub fn with_defaults(mut self) -> Self {
+        for action in Action::ALL {
+            self.bindings
+                .entry(action)
+                .or_insert_with(|| action.default_bindings());
+        }
+        self
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces the binding in `slot`, or adds one when the slot is past the end.
+    pub fn set(&mut self, action: Action, slot: usize, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        if slot < bindings.len() {
+            bindings[slot] = binding;
+        } else if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn remove(&mut self, action: Action, slot: usize) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            if slot < bindings.len() {
+                bindings.remove(slot);
+            }
+        }
+    }
+
+    /// Other actions that share a binding with `action`.
+    pub fn conflicts(&self, action: Action, binding: Binding) -> Vec<Action> {
+        self.bindings
+            .iter()
+            .filter(|(&other, bindings)| other != action && bindings.contains(&binding))
+            .map(|(&other, _)| other)
+            .collect()
+    }
+}
+
+impl ActionInput<'_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any(action, |binding| match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse.pressed(button),
+            Binding::Gamepad(button_type) => self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, button_type))
+            }),
+        })
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(action, |binding| match binding {
+            Binding::Key(key) => self.keys.just_pressed(key),
+            Binding::Mouse(button) => self.mouse.just_pressed(button),
+            Binding::Gamepad(button_type) => self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .just_pressed(GamepadButton::new(gamepad, button_type))
+            }),
+        })
+    }
+
+    /// Nothing counts as pressed while a key is being rebound.
+    fn any(&self, action: Action, is_down: impl Fn(Binding) -> bool) -> bool {
+        self.rebinding.action.is_none()
+            && self.map.get(action).iter().any(|&binding| is_down(binding))
+    }
+}
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .init_resource::<BindingsOpen>()
+            .add_systems(Update, bindings_ui.run_if(in_state(GameState::Settings)))
+            // After Update, so the captured key isn't also read as an action this frame
+            .add_systems(
+                PostUpdate,
+                capture_binding.run_if(in_state(GameState::Settings)),
+            )
+            .add_systems(OnExit(GameState::Settings), close_bindings);
+    }
+}
+
+fn bindings_ui(
+    mut contexts: EguiContexts,
+    mut open: ResMut<BindingsOpen>,
+    mut map: ResMut<ActionMap>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    if !open.0 {
+        return;
+    }
+
+    let mut still_open = true;
+    egui::Window::new("Key bindings")
+        .open(&mut still_open)
+        .collapsible(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Grid::new("bindings").striped(true).show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.name());
+                    ui.horizontal(|ui| {
+                        let bindings = map.get(action).to_vec();
+                        for (slot, binding) in bindings.into_iter().enumerate() {
+                            let waiting =
+                                rebinding.action == Some(action) && rebinding.slot == slot;
+                            let conflicts = map.conflicts(action, binding);
+                            let mut text = egui::RichText::new(if waiting {
+                                "...".to_string()
+                            } else {
+                                binding.name()
+                            });
+                            if !conflicts.is_empty() {
+                                text = text.color(egui::Color32::LIGHT_RED);
+                            }
+
+                            let mut button = ui.button(text);
+                            if !conflicts.is_empty() {
+                                let names: Vec<_> = conflicts.iter().map(Action::name).collect();
+                                button = button
+                                    .on_hover_text(format!("Also bound to {}", names.join(", ")));
+                            }
+                            if button.clicked() {
+                                *rebinding = Rebinding {
+                                    action: Some(action),
+                                    slot,
+                                };
+                            }
+                            if button.secondary_clicked() {
+                                map.remove(action, slot);
+                            }
+                        }
+
+                        let slot = map.get(action).len();
+                        let waiting = rebinding.action == Some(action) && rebinding.slot == slot;
+                        if ui.button(if waiting { "..." } else { "+" }).clicked() {
+                            *rebinding = Rebinding {
+                                action: Some(action),
+                                slot,
+                            };
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            if let Some(action) = rebinding.action {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Press a key or button for {}", action.name()));
+                    if ui.button("Cancel").clicked() {
+                        rebinding.action = None;
+                    }
+                });
+            } else {
+                ui.label("Click a binding to change it, right-click to remove it.");
+            }
+            if ui.button("Reset to defaults").clicked() {
+                *map = ActionMap::default();
+            }
+        });
+
+    if !still_open {
+        open.0 = false;
+        rebinding.action = None;
+    }
+}
+
+/// Binds the next key or button pressed to the action waiting on the bindings screen.
+/// Mouse buttons only count outside the egui windows, so clicking the UI doesn't bind them.
+fn capture_binding(
+    mut contexts: EguiContexts,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut map: ResMut<ActionMap>,
+) {
+    let Some(action) = rebinding.action else {
+        return;
+    };
+
+    let over_ui = contexts.ctx_mut().wants_pointer_input();
+    let pressed = keys
+        .get_just_pressed()
+        .next()
+        .map(|&key| Binding::Key(key))
+        .or_else(|| {
+            gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.button_type))
+        })
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .find(|_| !over_ui)
+                .map(|&button| Binding::Mouse(button))
+        });
+
+    if let Some(binding) = pressed {
+        map.set(action, rebinding.slot, binding);
+        rebinding.action = None;
+    }
+}
+
+fn close_bindings(mut open: ResMut<BindingsOpen>, mut rebinding: ResMut<Rebinding>) {
+    open.0 = false;
+    rebinding.action = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let map = ActionMap::default();
+        for action in Action::ALL {
+            assert!(!map.get(action).is_empty(), "{:?} is unbound", action);
+            for &binding in map.get(action) {
+                assert!(map.conflicts(action, binding).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn with_defaults_keeps_saved_bindings() {
+        let mut bindings = BTreeMap::new();
+        bindings.insert(Action::Dash, vec![Binding::Key(KeyCode::ShiftLeft)]);
+        let map = ActionMap { bindings }.with_defaults();
+
+        assert_eq!(map.get(Action::Dash), [Binding::Key(KeyCode::ShiftLeft)]);
+        assert_eq!(
+            map.get(Action::Pause),
+            ActionMap::default().get(Action::Pause)
+        );
+        assert_eq!(map.bindings.len(), Action::ALL.len());
+    }
+
+    #[test]
+    fn with_defaults_keeps_an_emptied_action() {
+        let mut bindings = BTreeMap::new();
+        bindings.insert(Action::TimeStep, Vec::new());
+        let map = ActionMap { bindings }.with_defaults();
+
+        assert!(map.get(Action::TimeStep).is_empty());
+    }
+
+    #[test]
+    fn set_replaces_a_slot() {
+        let mut map = ActionMap::default();
+        map.set(Action::Dash, 0, Binding::Key(KeyCode::ShiftLeft));
+
+        assert_eq!(map.get(Action::Dash)[0], Binding::Key(KeyCode::ShiftLeft));
+        assert_eq!(map.get(Action::Dash).len(), 2);
+    }
+
+    #[test]
+    fn set_past_the_end_adds_a_binding() {
+        let mut map = ActionMap::default();
+        map.set(Action::Dash, 10, Binding::Mouse(MouseButton::Right));
+
+        assert_eq!(
+            map.get(Action::Dash).last(),
+            Some(&Binding::Mouse(MouseButton::Right))
+        );
+        assert_eq!(map.get(Action::Dash).len(), 3);
+    }
+
+    #[test]
+    fn set_past_the_end_skips_a_duplicate() {
+        let mut map = ActionMap::default();
+        map.set(Action::Dash, 10, Binding::Key(KeyCode::Space));
+
+        assert_eq!(
+            map.get(Action::Dash),
+            ActionMap::default().get(Action::Dash)
+        );
+    }
+
+    #[test]
+    fn conflicts_lists_other_actions_only() {
+        let mut map = ActionMap::default();
+        map.set(Action::Dash, 0, Binding::Key(KeyCode::KeyW));
+
+        assert_eq!(
+            map.conflicts(Action::Dash, Binding::Key(KeyCode::KeyW)),
+            vec![Action::MoveUp]
+        );
+        assert_eq!(
+            map.conflicts(Action::MoveUp, Binding::Key(KeyCode::KeyW)),
+            vec![Action::Dash]
+        );
+        assert!(map
+            .conflicts(Action::Dash, Binding::Key(KeyCode::Space))
+            .is_empty());
+    }
+}
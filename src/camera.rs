@@ -10,7 +10,7 @@ pub struct FollowCameraPlugin;
 
 impl Plugin for FollowCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(PanCamPlugin::default())
+        app.add_plugins(PanCamPlugin)
             .add_systems(OnEnter(GameState::Loading), init_camera)
             .add_systems(
                 Update,
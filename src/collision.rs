@@ -2,12 +2,21 @@ use bevy::prelude::*;
 use kdtree::{distance::squared_euclidean, KdTree};
 
 use crate::{
-    components::{Damage, Health, NearestEnemy, Pierces},
+    combat::{apply_resistance, roll_hit},
+    components::{
+        CritChance, CritMultiplier, Damage, DamageType, DamageVariance, Direction, Health,
+        HurtCooldown, Knockback, Mass, NearestEnemy, Pierces,
+    },
     configs::*,
-    enemy::Enemy,
+    debug::GodMode,
+    enemy::{Enemy, EnemyType, HitStun, LastDamageSource},
+    events::{BulletHit, DamageSource, PlayerDamaged},
     gun::Bullet,
     player::Player,
+    resources::GameRng,
     state::GameState,
+    status::{OnHitEffects, StatusEffects},
+    timescale::simulation_running,
 };
 
 pub struct CollisionPlugin;
@@ -24,6 +33,7 @@ impl Default for EnemyKdTree {
         }
     }
 }
+
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<EnemyKdTree>().add_systems(
@@ -32,8 +42,10 @@ impl Plugin for CollisionPlugin {
                 update_enemy_kd_tree,
                 find_nearest_enemy,
                 handle_bullet_collision,
+                handle_enemy_collision,
             )
-                .run_if(in_state(GameState::InGame)),
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
         );
     }
 }
@@ -81,16 +93,57 @@ fn find_nearest_enemy(
     }
 }
 
-fn handle_bullet_collision(
-    mut bullet_query: Query<(&Transform, &Damage, &mut Pierces), With<Bullet>>,
+#[allow(clippy::type_complexity)]
+pub fn handle_bullet_collision(
+    mut bullet_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Direction,
+            &Damage,
+            &CritChance,
+            &CritMultiplier,
+            &DamageVariance,
+            &DamageType,
+            &Knockback,
+            &OnHitEffects,
+            &mut Pierces,
+        ),
+        With<Bullet>,
+    >,
     enemy_kdtree: Res<EnemyKdTree>,
-    mut enemy_query: Query<&mut Health, With<Enemy>>,
+    mut enemy_query: Query<
+        (
+            &mut Health,
+            &mut StatusEffects,
+            &mut HitStun,
+            &mut LastDamageSource,
+            &Mass,
+            &EnemyType,
+        ),
+        With<Enemy>,
+    >,
+    mut rng: ResMut<GameRng>,
+    mut hit_events: EventWriter<BulletHit>,
 ) {
     if bullet_query.is_empty() || enemy_query.is_empty() {
         return;
     }
 
-    for (transform, damage, mut pierces) in bullet_query.iter_mut() {
+    for (
+        entity_bullet,
+        transform,
+        direction,
+        damage,
+        crit_chance,
+        crit_multiplier,
+        variance,
+        damage_type,
+        knockback,
+        on_hit,
+        mut pierces,
+    ) in bullet_query.iter_mut()
+    {
         let bullet_position = transform.translation;
         let search_point = [bullet_position.x, bullet_position.y];
 
@@ -102,12 +155,80 @@ fn handle_bullet_collision(
             if let Some((distance, &entity_enemy)) = nearest_results.first() {
                 if distance.sqrt() <= BULLET_SPEED {
                     // Handle collision
-                    if let Ok(mut heath) = enemy_query.get_mut(entity_enemy) {
-                        heath.value -= damage.value;
+                    if let Ok((
+                        mut heath,
+                        mut status,
+                        mut hit_stun,
+                        mut last_source,
+                        mass,
+                        enemy_type,
+                    )) = enemy_query.get_mut(entity_enemy)
+                    {
+                        let hit = roll_hit(
+                            &mut rng.rng,
+                            damage.value,
+                            crit_chance.value,
+                            crit_multiplier.value,
+                            variance.value,
+                        );
+                        let damage =
+                            apply_resistance(hit.damage, enemy_type.resistance(*damage_type));
+                        heath.value -= damage;
+                        last_source.value = Some(DamageSource::Bullet(entity_bullet));
+                        on_hit.roll(&mut rng.rng, &mut status);
+                        hit_stun.push(direction.value.truncate(), knockback.value, mass.value);
                         pierces.value = pierces.value.saturating_sub(1);
+                        hit_events.send(BulletHit {
+                            bullet: entity_bullet,
+                            enemy: entity_enemy,
+                            position: bullet_position.truncate(),
+                            damage,
+                            damage_type: *damage_type,
+                            crit: hit.crit,
+                        });
                     }
                 }
             }
         }
     }
 }
+
+fn handle_enemy_collision(
+    time: Res<Time>,
+    enemy_kdtree: Res<EnemyKdTree>,
+    god_mode: Res<GodMode>,
+    mut player_query: Query<(Entity, &Transform, &mut Health, &mut HurtCooldown), With<Player>>,
+    mut damage_events: EventWriter<PlayerDamaged>,
+) {
+    let Ok((player, transform, mut health, mut cooldown)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    cooldown.timer = (cooldown.timer - time.delta_seconds()).max(0.0);
+    if cooldown.timer > 0.0 || god_mode.0 {
+        return;
+    }
+
+    let player_position = transform.translation.truncate();
+    let Ok(touching) = enemy_kdtree.tree.within(
+        &[player_position.x, player_position.y],
+        ENEMY_CONTACT_RADIUS * ENEMY_CONTACT_RADIUS,
+        &squared_euclidean,
+    ) else {
+        return;
+    };
+    if touching.is_empty() {
+        return;
+    }
+
+    for (_, &enemy) in touching.iter() {
+        health.value = (health.value - ENEMY_DAMAGE).max(0.0);
+        damage_events.send(PlayerDamaged {
+            player,
+            position: player_position,
+            damage: ENEMY_DAMAGE,
+            source: DamageSource::Contact(enemy),
+        });
+    }
+    cooldown.timer = PLAYER_HURT_COOLDOWN;
+}
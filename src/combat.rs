@@ -0,0 +1,102 @@
+use rand::Rng;
+
+pub struct HitRoll {
+    pub damage: f32,
+    pub crit: bool,
+}
+
+/// Rolls the damage of a single hit: variance is applied first, then the crit check.
+pub fn roll_hit(
+    rng: &mut impl Rng,
+    damage: f32,
+    crit_chance: f32,
+    crit_multiplier: f32,
+    variance: f32,
+) -> HitRoll {
+    let mut damage = damage;
+    if variance > 0.0 {
+        damage *= 1.0 + rng.gen_range(-variance..=variance);
+    }
+
+    let crit = rng.gen::<f32>() < crit_chance;
+    if crit {
+        damage *= crit_multiplier;
+    }
+
+    HitRoll {
+        damage: damage.max(0.0),
+        crit,
+    }
+}
+
+/// Scales damage by a resistance: positive values resist, negative values are weaknesses.
+pub fn apply_resistance(damage: f32, resistance: f32) -> f32 {
+    damage * (1.0 - resistance.min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn never_crits_at_zero_chance() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let hit = roll_hit(&mut rng, 10.0, 0.0, 2.0, 0.0);
+            assert!(!hit.crit);
+            assert_eq!(hit.damage, 10.0);
+        }
+    }
+
+    #[test]
+    fn always_crits_at_full_chance() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for _ in 0..1000 {
+            let hit = roll_hit(&mut rng, 10.0, 1.0, 2.5, 0.0);
+            assert!(hit.crit);
+            assert_eq!(hit.damage, 25.0);
+        }
+    }
+
+    #[test]
+    fn variance_stays_within_bounds() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for _ in 0..1000 {
+            let hit = roll_hit(&mut rng, 10.0, 0.0, 2.0, 0.2);
+            assert!((8.0..=12.0).contains(&hit.damage), "{}", hit.damage);
+        }
+    }
+
+    #[test]
+    fn variance_is_applied_before_the_crit() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        for _ in 0..1000 {
+            let hit = roll_hit(&mut rng, 10.0, 1.0, 2.0, 0.2);
+            assert!((16.0..=24.0).contains(&hit.damage), "{}", hit.damage);
+        }
+    }
+
+    #[test]
+    fn damage_is_never_negative() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        for _ in 0..1000 {
+            let hit = roll_hit(&mut rng, 10.0, 0.0, 2.0, 1.5);
+            assert!(hit.damage >= 0.0);
+        }
+    }
+
+    #[test]
+    fn resistance_scales_damage() {
+        assert_eq!(apply_resistance(10.0, 0.0), 10.0);
+        assert_eq!(apply_resistance(10.0, 0.5), 5.0);
+        assert_eq!(apply_resistance(10.0, -0.5), 15.0);
+    }
+
+    #[test]
+    fn resistance_caps_at_immunity() {
+        assert_eq!(apply_resistance(10.0, 1.0), 0.0);
+        assert_eq!(apply_resistance(10.0, 3.0), 0.0);
+    }
+}
@@ -1,19 +1,37 @@
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
 use bevy::{prelude::*, time::Stopwatch};
+use serde::{Deserialize, Serialize};
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Speed {
     pub value: f32,
 }
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Health {
     pub value: f32,
 }
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct MaxHealth {
+    pub value: f32,
+}
+/// Time left before the player can take contact damage again.
+#[derive(Component, Default, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct HurtCooldown {
+    pub timer: f32,
+}
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct GunBag {
     pub size: u32,
     pub capacity: u32,
 }
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component, MapEntities)]
 pub struct NearestEnemy {
     pub entity: Option<Entity>,
     pub distance: f32,
@@ -28,35 +46,85 @@ impl Default for NearestEnemy {
     }
 }
 
-#[derive(Component)]
+impl MapEntities for NearestEnemy {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.entity = self.entity.map(|entity| entity_mapper.map_entity(entity));
+    }
+}
+
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct AttackSpeed {
     pub timer: Stopwatch,
     pub interval: f32,
 }
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct AttackSpeedPercent {
     pub value: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Damage {
     pub value: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Range {
     pub value: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Direction {
     pub value: Vec3,
 }
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Position {
     pub value: Vec2,
 }
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub struct Pierces {
     pub value: u32,
 }
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct CritChance {
+    pub value: f32,
+}
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct CritMultiplier {
+    pub value: f32,
+}
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct DamageVariance {
+    pub value: f32,
+}
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Knockback {
+    pub value: f32,
+}
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Mass {
+    pub value: f32,
+}
+#[derive(
+    Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect,
+)]
+#[reflect(Component)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Ice,
+    Poison,
+    Lightning,
+}
@@ -1,18 +1,102 @@
 use bevy::prelude::Vec2;
 
+use crate::components::DamageType;
+
 // Window
 pub const WINDOW_SIZE: Vec2 = Vec2::new(720.0, 480.0);
 
 // Sprites
-pub const SPRITE_SHEET_PATH: &str = "assets.png";
+pub const ATLAS_MANIFEST_PATH: &str = "sprites.atlas.ron";
+pub const FONT_PATH: &str = "monogram.ttf";
+pub const ABILITY_BOOK_PATH: &str = "player.abilities.ron";
 pub const SPRITE_SCALE_FACTOR: f32 = 3.0;
-pub const TILE_WIDTH: u32 = 16;
-pub const TILE_HEIGHT: u32 = 16;
-pub const SPRITE_SHEET_WIDTH: u32 = 8;
-pub const SPRITE_SHEET_HEIGHT: u32 = 8;
+pub const SPRITE_PLAYER: &str = "player";
+pub const SPRITE_BULLET: &str = "bullet";
+pub const SPRITE_GUN: &str = "gun";
+pub const SPRITE_ENEMY_GREEN: &str = "enemy_green";
+pub const SPRITE_ENEMY_RED: &str = "enemy_red";
+pub const SPRITE_ENEMY_SKIN: &str = "enemy_skin";
+/// Sprites the code looks up by name. Loading fails if the manifest is missing any of them.
+pub const REQUIRED_SPRITES: [&str; 6] = [
+    SPRITE_PLAYER,
+    SPRITE_BULLET,
+    SPRITE_GUN,
+    SPRITE_ENEMY_GREEN,
+    SPRITE_ENEMY_RED,
+    SPRITE_ENEMY_SKIN,
+];
+
+// Animation
+/// Distance per frame below which an entity counts as standing still.
+pub const ANIMATION_WALK_THRESHOLD: f32 = 0.1;
+
+// Overlays
+pub const HEALTH_BAR_SIZE: Vec2 = Vec2::new(14.0, 2.0);
+/// Height above the owner's centre, in the owner's unscaled sprite pixels.
+pub const HEALTH_BAR_OFFSET: f32 = 10.0;
+pub const HEALTH_BAR_COLOR: (u8, u8, u8) = (200, 40, 40);
+pub const HEALTH_BAR_BACKGROUND_COLOR: (u8, u8, u8) = (30, 30, 30);
+pub const DAMAGE_NUMBER_POOL_SIZE: usize = 64;
+pub const DAMAGE_NUMBER_LIFETIME: f32 = 0.8;
+pub const DAMAGE_NUMBER_RISE_SPEED: f32 = 40.0;
+pub const DAMAGE_NUMBER_FONT_SIZE: f32 = 24.0;
+pub const DAMAGE_NUMBER_CRIT_FONT_SIZE: f32 = 36.0;
+pub const DAMAGE_NUMBER_STATUS_FONT_SIZE: f32 = 18.0;
+pub const DAMAGE_NUMBER_COLOR: (u8, u8, u8) = (255, 255, 255);
+pub const DAMAGE_NUMBER_CRIT_COLOR: (u8, u8, u8) = (255, 210, 40);
+pub const DAMAGE_NUMBER_BURN_COLOR: (u8, u8, u8) = (255, 120, 30);
+pub const DAMAGE_NUMBER_POISON_COLOR: (u8, u8, u8) = (120, 220, 60);
+
+// HUD
+pub const HUD_FONT_SIZE: f32 = 24.0;
+pub const HUD_MARGIN: f32 = 8.0;
+pub const HUD_BAR_SIZE: Vec2 = Vec2::new(200.0, 10.0);
+pub const HUD_XP_BAR_COLOR: (u8, u8, u8) = (90, 200, 250);
+pub const HUD_BAR_BACKGROUND_COLOR: (u8, u8, u8) = (30, 30, 30);
 
 // Player
 pub const MINIMUM_DISTANCE_CURSOR_PLAYER: f32 = 50.0;
+pub const GAMEPAD_STICK_DEADZONE: f32 = 0.2;
+pub const PLAYER_HURT_COOLDOWN: f32 = 0.5;
+pub const PLAYER_BASE_HEALTH: f32 = 100.0;
+pub const PLAYER_BASE_SPEED: f32 = 2.5;
+pub const GUN_BAG_BASE_CAPACITY: u32 = 6;
+
+// Dash
+/// Distance covered per frame while dashing.
+pub const DASH_SPEED: f32 = 12.0;
+pub const DASH_DURATION: f32 = 0.15;
+/// Seconds of immunity to contact damage from the start of a dash.
+pub const DASH_INVULNERABILITY: f32 = 0.3;
+pub const DASH_BASE_COOLDOWN: f32 = 2.0;
+pub const DASH_MIN_COOLDOWN: f32 = 0.5;
+pub const DASH_BASE_CHARGES: u32 = 1;
+pub const DASH_MAX_CHARGES: u32 = 3;
+/// Player sprite opacity during a dash.
+pub const DASH_ALPHA: f32 = 0.5;
+
+// Abilities
+/// One slot per ability key. The ability book may not define more.
+pub const ABILITY_SLOTS: usize = 4;
+pub const ABILITY_BAR_SLOT_WIDTH: f32 = 100.0;
+
+// Autopilot
+pub const AUTOPILOT_DANGER_RADIUS: f32 = 200.0;
+pub const AUTOPILOT_FLEE_WEIGHT: f32 = 1.0;
+pub const AUTOPILOT_PICKUP_WEIGHT: f32 = 0.6;
+pub const AUTOPILOT_WALL_MARGIN: f32 = 100.0;
+
+// Pickups
+pub const XP_ORB_SIZE: f32 = 8.0;
+pub const XP_ORB_COLOR: (u8, u8, u8) = (90, 200, 250);
+pub const PICKUP_RADIUS: f32 = 40.0;
+pub const PICKUP_MAGNET_RADIUS: f32 = 120.0;
+pub const PICKUP_MAGNET_SPEED: f32 = 6.0;
+
+// Levels
+pub const LEVEL_BASE_XP: f32 = 10.0;
+/// Each level needs this many times the XP of the one before.
+pub const LEVEL_XP_GROWTH: f32 = 1.25;
 
 // Enemy
 pub const MAX_NUM_ENEMIES: usize = 500;
@@ -21,6 +105,7 @@ pub const SPAWN_RATE_PER_SECOND: usize = 5;
 pub const ENEMY_HEALTH: f32 = 100.0;
 pub const ENEMY_SPAWN_INTERVAL: f32 = 1.0;
 pub const ENEMY_SPEED: f32 = 1.0;
+pub const ENEMY_CONTACT_RADIUS: f32 = 30.0;
 
 // Colors
 pub const BACKGROUND_SIZE: Vec2 = Vec2::new(1920.0, 1080.0);
@@ -28,13 +113,26 @@ pub const BACKGROUND_OFFSET: Vec2 = Vec2::new(140.0, 70.0);
 pub const BACKGROUND_COLOR: (u8, u8, u8) = (197, 204, 184);
 
 // Weapon
-pub const GUN_OFFSET: [Vec2; 6] = [
+/// One slot per gun the bag can ever hold, base capacity plus every capacity upgrade.
+pub const GUN_OFFSET: [Vec2; 10] = [
     Vec2::new(35.0, -15.0),
     Vec2::new(-45.0, -15.0),
     Vec2::new(35.0, 5.0),
     Vec2::new(-45.0, 5.0),
     Vec2::new(35.0, 25.0),
     Vec2::new(-45.0, 25.0),
+    Vec2::new(35.0, -35.0),
+    Vec2::new(-45.0, -35.0),
+    Vec2::new(35.0, 45.0),
+    Vec2::new(-45.0, 45.0),
+];
+pub const GUN_DAMAGE_TYPES: [DamageType; 6] = [
+    DamageType::Physical,
+    DamageType::Fire,
+    DamageType::Ice,
+    DamageType::Poison,
+    DamageType::Lightning,
+    DamageType::Physical,
 ];
 
 // Bullet
@@ -44,3 +142,69 @@ pub const BULLET_RANGE: f32 = 200.0;
 pub const BULLET_DAMAGE: f32 = 20.0;
 pub const BULLET_PIERCES: u32 = 1;
 pub const BULLETS_PER_SHOT: usize = 1;
+
+// Projectile modifiers
+pub const HOMING_TURN_RATE: f32 = 0.15;
+pub const HOMING_RADIUS: f32 = 150.0;
+pub const CHAIN_RANGE: f32 = 150.0;
+pub const SPLIT_FRAGMENTS: u32 = 3;
+pub const SPLIT_DAMAGE_FRACTION: f32 = 0.5;
+pub const SPLIT_RANGE: f32 = 100.0;
+
+// Knockback
+pub const BULLET_KNOCKBACK: f32 = 4.0;
+pub const KNOCKBACK_DAMPING: f32 = 0.8;
+pub const HIT_STUN_DURATION: f32 = 0.15;
+
+// Critical hits
+pub const CRIT_CHANCE: f32 = 0.05;
+pub const CRIT_MULTIPLIER: f32 = 1.5;
+pub const DAMAGE_VARIANCE: f32 = 0.1;
+
+// Status effects
+pub const STATUS_TICK_INTERVAL: f32 = 0.5;
+pub const POISON_MAX_STACKS: u32 = 5;
+/// Chance for a bullet to apply the status that goes with its damage type.
+pub const WEAPON_PROC_CHANCE: f32 = 0.2;
+pub const BURN_DAMAGE_PER_SECOND: f32 = 10.0;
+pub const BURN_DURATION: f32 = 3.0;
+pub const POISON_DAMAGE_PER_SECOND: f32 = 6.0;
+pub const POISON_DURATION: f32 = 4.0;
+pub const SLOW_FRACTION: f32 = 0.4;
+pub const SLOW_DURATION: f32 = 2.0;
+pub const STUN_DURATION: f32 = 0.5;
+
+// Time scale
+pub const TIME_SCALE_MIN: f32 = 0.1;
+pub const TIME_SCALE_MAX: f32 = 4.0;
+/// Each slower or faster key press divides or multiplies the scale by this.
+pub const TIME_SCALE_KEY_FACTOR: f32 = 2.0;
+/// Simulated time a single step advances by, one frame at 60 fps.
+pub const TIME_STEP_SECONDS: f32 = 1.0 / 60.0;
+
+// Profile
+pub const PROFILE_DIRECTORY: &str = "shooter-rs";
+pub const PROFILE_FILE_NAME: &str = "profile.ron";
+pub const PROFILE_AUTOSAVE_INTERVAL: f32 = 30.0;
+pub const PROFILE_BEST_TIMES: usize = 10;
+pub const RUN_SNAPSHOT_FILE_NAME: &str = "run.ron";
+
+// Offline progress
+pub const OFFLINE_XP_PER_KILL: f32 = 2.0;
+pub const OFFLINE_EFFICIENCY: f32 = 0.25;
+pub const OFFLINE_MAX_HOURS: f32 = 8.0;
+pub const OFFLINE_MIN_SECONDS: u64 = 60;
+
+// Shop
+pub const GOLD_PER_KILL: u64 = 1;
+pub const SHOP_COST_GROWTH: f32 = 1.5;
+pub const SHOP_MAX_BOUNCES_AND_CHAINS: u32 = 3;
+
+// Prestige
+pub const PRESTIGE_BONUS_STEP: f32 = 0.1;
+pub const PRESTIGE_GOLD_DIVISOR: u64 = 1000;
+
+// Scenes
+pub const SCENE_DIRECTORY: &str = "scenes";
+pub const SCENE_EXTENSION: &str = "scn.ron";
+pub const TEST_ARENA_SCENE: &str = "test_arena";
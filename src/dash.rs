@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::{Action, ActionInput};
+use crate::components::HurtCooldown;
+use crate::configs::*;
+use crate::input::MovementInput;
+use crate::player::{clamp_to_arena, update_player_movement, Player};
+use crate::state::GameState;
+use crate::timescale::{simulation_running, TimeScale};
+
+pub struct DashPlugin;
+
+/// A burst of speed in the movement direction. Charges come back one at a time,
+/// each taking `cooldown` seconds.
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Dash {
+    pub charges: u32,
+    pub max_charges: u32,
+    pub cooldown: f32,
+    /// Seconds until the next charge comes back.
+    pub recharge: f32,
+    /// Seconds left in the dash currently moving the player.
+    pub remaining: f32,
+    pub direction: Vec2,
+}
+
+impl Dash {
+    pub fn new(max_charges: u32, cooldown: f32) -> Self {
+        Self {
+            charges: max_charges,
+            max_charges,
+            cooldown,
+            recharge: 0.0,
+            remaining: 0.0,
+            direction: Vec2::ZERO,
+        }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.remaining > 0.0
+    }
+}
+
+impl Plugin for DashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (start_dash, move_dashing_player, recharge_dash)
+                .chain()
+                .after(update_player_movement)
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
+        );
+    }
+}
+
+/// Dashes where the player is heading, or the way they face when standing still.
+fn start_dash(
+    mut contexts: EguiContexts,
+    actions: ActionInput,
+    movement: Res<MovementInput>,
+    mut player_query: Query<(&mut Dash, &mut HurtCooldown, &Sprite), With<Player>>,
+) {
+    if !actions.just_pressed(Action::Dash) || contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let Ok((mut dash, mut hurt_cooldown, sprite)) = player_query.get_single_mut() else {
+        return;
+    };
+    if dash.charges == 0 || dash.is_dashing() {
+        return;
+    }
+
+    let facing = if sprite.flip_x { Vec2::NEG_X } else { Vec2::X };
+    dash.direction = movement.direction.try_normalize().unwrap_or(facing);
+    dash.remaining = DASH_DURATION;
+    if dash.charges == dash.max_charges {
+        dash.recharge = dash.cooldown;
+    }
+    dash.charges -= 1;
+
+    // Contact damage already waits out the hurt cooldown, so reuse it for the i-frames
+    hurt_cooldown.timer = hurt_cooldown.timer.max(DASH_INVULNERABILITY);
+}
+
+fn move_dashing_player(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut player_query: Query<(&mut Transform, &mut Sprite, &mut Dash), With<Player>>,
+) {
+    let Ok((mut transform, mut sprite, mut dash)) = player_query.get_single_mut() else {
+        return;
+    };
+    if !dash.is_dashing() {
+        return;
+    }
+
+    transform.translation += (dash.direction * DASH_SPEED * time_scale.factor()).extend(0.0);
+    transform.translation = clamp_to_arena(transform.translation);
+
+    dash.remaining -= time.delta_seconds();
+    let alpha = if dash.is_dashing() { DASH_ALPHA } else { 1.0 };
+    sprite.color.set_alpha(alpha);
+}
+
+fn recharge_dash(time: Res<Time>, mut player_query: Query<&mut Dash, With<Player>>) {
+    let Ok(mut dash) = player_query.get_single_mut() else {
+        return;
+    };
+    if dash.charges >= dash.max_charges {
+        return;
+    }
+
+    dash.recharge -= time.delta_seconds();
+    if dash.recharge <= 0.0 {
+        dash.charges += 1;
+        dash.recharge += dash.cooldown;
+    }
+}
@@ -0,0 +1,672 @@
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::bindings::{Action, ActionInput};
+use crate::components::{
+    AttackSpeed, AttackSpeedPercent, CritChance, CritMultiplier, Damage, DamageType,
+    DamageVariance, GunBag, Health, Knockback, MaxHealth, Pierces, Range, Speed,
+};
+use crate::configs::{GUN_OFFSET, TEST_ARENA_SCENE, TIME_SCALE_MAX, TIME_SCALE_MIN};
+use crate::dash::Dash;
+use crate::enemy::{spawn_enemy, Enemy, EnemyType};
+use crate::gun::{Bullet, Gun};
+use crate::pickup::{Experience, XpOrb};
+use crate::player::Player;
+use crate::profile::Profile;
+use crate::projectile::{Homing, ProjectileModifiers, Split};
+use crate::resources::{CursorPosition, GlobalTextureAtlas};
+use crate::scene::{scene_path, LoadScene, SaveScene};
+use crate::state::GameState;
+use crate::timescale::TimeScale;
+
+pub struct DebugPlugin;
+
+/// The player takes no contact damage while set.
+#[derive(Resource, Default)]
+pub struct GodMode(pub bool);
+
+#[derive(Resource)]
+pub struct DebugConsole {
+    pub open: bool,
+    pub input: String,
+    pub log: Vec<String>,
+    /// Enemy type spawned at the cursor with F2.
+    pub spawn_type: EnemyType,
+}
+
+/// A line of console input. Send one to script the console from code.
+#[derive(Event)]
+pub struct ConsoleCommand(pub String);
+
+#[derive(Debug, PartialEq)]
+pub enum DebugCommand {
+    Help,
+    Count,
+    Spawn { enemy_type: EnemyType, count: u32 },
+    Xp(f32),
+    Gold(u64),
+    God(Option<bool>),
+    TimeScale(f32),
+    Step,
+    Heal,
+    KillAll,
+    Set { stat: String, value: f32 },
+    SaveScene(String),
+    LoadScene(String),
+}
+
+const HELP: &str = "help | count | spawn <green|red|skin> [count] | xp <amount> | gold <amount> \
+| god [on|off] | timescale <speed> | step | heal | killall | set <stat> <value> | save <scene> \
+| load <scene>";
+
+const PLAYER_STATS: &str = "health, max_health, speed, guns, capacity, damage, attack_speed, \
+range, pierces, crit_chance, crit_multiplier, knockback, xp, dash_charges, dash_cooldown, \
+homing, bounces, chains, split";
+
+/// Stats that guns copy from the player when they spawn, so `set` also passes them to
+/// guns already in play.
+const MODIFIER_STATS: [&str; 4] = ["homing", "bounces", "chains", "split"];
+
+type PlayerComponents = (
+    &'static mut Health,
+    &'static mut MaxHealth,
+    &'static mut Speed,
+    &'static mut GunBag,
+    &'static mut Experience,
+    (
+        &'static mut Damage,
+        &'static mut AttackSpeedPercent,
+        &'static mut Range,
+        &'static mut Pierces,
+        &'static mut CritChance,
+        &'static mut CritMultiplier,
+        &'static mut Knockback,
+    ),
+    &'static mut Dash,
+    &'static mut ProjectileModifiers,
+);
+
+type GunComponents = (
+    Entity,
+    &'static mut AttackSpeed,
+    &'static mut Damage,
+    &'static mut Range,
+    &'static mut Pierces,
+    &'static mut CritChance,
+    &'static mut CritMultiplier,
+    &'static mut DamageVariance,
+    &'static mut DamageType,
+    &'static mut Knockback,
+    &'static mut ProjectileModifiers,
+);
+
+impl Default for DebugConsole {
+    fn default() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            log: Vec::new(),
+            spawn_type: EnemyType::Green,
+        }
+    }
+}
+
+impl std::str::FromStr for DebugCommand {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Err("empty command".to_string());
+        };
+        let args: Vec<&str> = words.collect();
+        let number = |index: usize| -> Result<f32, String> {
+            let arg = args
+                .get(index)
+                .ok_or_else(|| format!("{} needs a number", name))?;
+            arg.parse::<f32>()
+                .map_err(|_| format!("\"{}\" is not a number", arg))
+        };
+
+        let command = match name.to_lowercase().as_str() {
+            "help" => DebugCommand::Help,
+            "count" => DebugCommand::Count,
+            "spawn" => {
+                let type_name = args.first().ok_or("spawn needs an enemy type")?;
+                let enemy_type = EnemyType::from_name(type_name)
+                    .ok_or_else(|| format!("unknown enemy type \"{}\"", type_name))?;
+                let count = if args.len() > 1 { number(1)? as u32 } else { 1 };
+                DebugCommand::Spawn { enemy_type, count }
+            }
+            "xp" => DebugCommand::Xp(number(0)?),
+            "gold" => DebugCommand::Gold(number(0)?.max(0.0) as u64),
+            "god" => match args.first().copied() {
+                None => DebugCommand::God(None),
+                Some("on") => DebugCommand::God(Some(true)),
+                Some("off") => DebugCommand::God(Some(false)),
+                Some(arg) => return Err(format!("god takes on or off, not \"{}\"", arg)),
+            },
+            "timescale" => DebugCommand::TimeScale(number(0)?.max(0.0)),
+            "step" => DebugCommand::Step,
+            "heal" => DebugCommand::Heal,
+            "killall" => DebugCommand::KillAll,
+            "set" => {
+                let stat = args.first().ok_or("set needs a stat name")?;
+                DebugCommand::Set {
+                    stat: stat.to_lowercase(),
+                    value: number(1)?,
+                }
+            }
+            "save" | "load" => {
+                let scene = args
+                    .first()
+                    .ok_or_else(|| format!("{} needs a scene name", name))?
+                    .to_string();
+                if name.eq_ignore_ascii_case("save") {
+                    DebugCommand::SaveScene(scene)
+                } else {
+                    DebugCommand::LoadScene(scene)
+                }
+            }
+            _ => return Err(format!("unknown command \"{}\", try help", name)),
+        };
+        Ok(command)
+    }
+}
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GodMode>()
+            .init_resource::<DebugConsole>()
+            .add_event::<ConsoleCommand>()
+            .add_systems(Update, toggle_debug_console)
+            .add_systems(
+                Update,
+                (
+                    (debug_console_ui, spawn_at_cursor)
+                        .run_if(|console: Res<DebugConsole>| console.open),
+                    // Commands sent by scripts or other systems run with the console closed
+                    run_console_commands,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame).or_else(in_state(GameState::Paused))),
+            );
+    }
+}
+
+fn toggle_debug_console(input: ActionInput, mut console: ResMut<DebugConsole>) {
+    if input.just_pressed(Action::ToggleConsole) {
+        console.open = !console.open;
+    }
+}
+
+fn spawn_at_cursor(
+    input: ActionInput,
+    console: Res<DebugConsole>,
+    mut command_events: EventWriter<ConsoleCommand>,
+) {
+    if input.just_pressed(Action::SpawnAtCursor) {
+        command_events.send(ConsoleCommand(format!(
+            "spawn {}",
+            console.spawn_type.name()
+        )));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn debug_console_ui(
+    mut contexts: EguiContexts,
+    mut console: ResMut<DebugConsole>,
+    mut god_mode: ResMut<GodMode>,
+    mut time_scale: ResMut<TimeScale>,
+    mut command_events: EventWriter<ConsoleCommand>,
+    enemy_query: Query<(), With<Enemy>>,
+    bullet_query: Query<(), With<Bullet>>,
+    orb_query: Query<(), With<XpOrb>>,
+    mut player_query: Query<PlayerComponents, (With<Player>, Without<Gun>)>,
+    mut gun_query: Query<GunComponents, (With<Gun>, Without<Player>)>,
+) {
+    let console = &mut *console;
+    let mut submitted = None;
+
+    egui::Window::new("Debug").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!(
+            "Enemies: {}  Bullets: {}  Guns: {}  XP orbs: {}",
+            enemy_query.iter().count(),
+            bullet_query.iter().count(),
+            gun_query.iter().count(),
+            orb_query.iter().count()
+        ));
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut god_mode.0, "God mode");
+            ui.checkbox(&mut time_scale.frozen, "Frozen");
+            if ui.button("Step").clicked() {
+                time_scale.step();
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut time_scale.scale, TIME_SCALE_MIN..=TIME_SCALE_MAX)
+                .logarithmic(true)
+                .text("Time scale"),
+        );
+
+        ui.horizontal(|ui| {
+            let load_arena = format!("load {}", TEST_ARENA_SCENE);
+            for command in ["xp 100", "gold 100", "heal", "killall", load_arena.as_str()] {
+                if ui.button(command).clicked() {
+                    submitted = Some(command.to_string());
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("F2 spawns:");
+            for enemy_type in EnemyType::ALL {
+                ui.radio_value(&mut console.spawn_type, enemy_type, enemy_type.name());
+            }
+        });
+
+        if let Ok(player) = player_query.get_single_mut() {
+            egui::CollapsingHeader::new("Player").show(ui, |ui| {
+                player_editor(ui, player);
+            });
+        }
+
+        egui::CollapsingHeader::new("Guns").show(ui, |ui| {
+            for (slot, gun) in gun_query.iter_mut().enumerate() {
+                egui::CollapsingHeader::new(format!("Gun {}", slot + 1))
+                    .id_salt(gun.0)
+                    .show(ui, |ui| gun_editor(ui, gun));
+            }
+        });
+
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .max_height(120.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in console.log.iter() {
+                    ui.monospace(line);
+                }
+            });
+        let response = ui.text_edit_singleline(&mut console.input);
+        if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+            submitted = Some(std::mem::take(&mut console.input));
+            response.request_focus();
+        }
+    });
+
+    if let Some(line) = submitted {
+        command_events.send(ConsoleCommand(line));
+    }
+}
+
+fn player_editor(ui: &mut egui::Ui, player: QueryItem<PlayerComponents>) {
+    let (
+        mut health,
+        mut max_health,
+        mut speed,
+        mut gun_bag,
+        mut experience,
+        (
+            mut damage,
+            mut attack_speed,
+            mut range,
+            mut pierces,
+            mut crit_chance,
+            mut crit_multiplier,
+            mut knockback,
+        ),
+        mut dash,
+        _,
+    ) = player;
+
+    egui::Grid::new("player_editor").show(ui, |ui| {
+        let row = |ui: &mut egui::Ui, label: &str, value: &mut f32| {
+            ui.label(label);
+            ui.add(egui::DragValue::new(value).speed(0.1));
+            ui.end_row();
+        };
+        row(ui, "Health", &mut health.value);
+        row(ui, "Max health", &mut max_health.value);
+        row(ui, "Speed", &mut speed.value);
+        row(ui, "XP", &mut experience.value);
+        row(ui, "Bonus damage", &mut damage.value);
+        row(ui, "Bonus attack speed %", &mut attack_speed.value);
+        row(ui, "Bonus range", &mut range.value);
+        row(ui, "Bonus crit chance", &mut crit_chance.value);
+        row(ui, "Bonus crit multiplier", &mut crit_multiplier.value);
+        row(ui, "Bonus knockback", &mut knockback.value);
+        row(ui, "Dash cooldown", &mut dash.cooldown);
+
+        ui.label("Bonus pierces");
+        ui.add(egui::DragValue::new(&mut pierces.value));
+        ui.end_row();
+        ui.label("Guns");
+        let capacity = gun_bag.capacity;
+        ui.add(egui::DragValue::new(&mut gun_bag.size).range(1..=capacity));
+        ui.end_row();
+        ui.label("Dash charges");
+        ui.add(egui::DragValue::new(&mut dash.max_charges));
+        ui.end_row();
+        ui.label("Gun capacity");
+        ui.add(egui::DragValue::new(&mut gun_bag.capacity).range(1..=GUN_OFFSET.len() as u32));
+        ui.end_row();
+    });
+}
+
+fn gun_editor(ui: &mut egui::Ui, gun: QueryItem<GunComponents>) {
+    let (
+        entity,
+        mut attack_speed,
+        mut damage,
+        mut range,
+        mut pierces,
+        mut crit_chance,
+        mut crit_multiplier,
+        mut damage_variance,
+        mut damage_type,
+        mut knockback,
+        mut modifiers,
+    ) = gun;
+
+    egui::Grid::new(("gun_editor", entity)).show(ui, |ui| {
+        let row = |ui: &mut egui::Ui, label: &str, value: &mut f32| {
+            ui.label(label);
+            ui.add(egui::DragValue::new(value).speed(0.01));
+            ui.end_row();
+        };
+        row(ui, "Interval", &mut attack_speed.interval);
+        row(ui, "Damage", &mut damage.value);
+        row(ui, "Range", &mut range.value);
+        row(ui, "Crit chance", &mut crit_chance.value);
+        row(ui, "Crit multiplier", &mut crit_multiplier.value);
+        row(ui, "Damage variance", &mut damage_variance.value);
+        row(ui, "Knockback", &mut knockback.value);
+
+        ui.label("Pierces");
+        ui.add(egui::DragValue::new(&mut pierces.value));
+        ui.end_row();
+        ui.label("Bounces");
+        ui.add(egui::DragValue::new(&mut modifiers.bounces));
+        ui.end_row();
+        ui.label("Chains");
+        ui.add(egui::DragValue::new(&mut modifiers.chains));
+        ui.end_row();
+        ui.label("Homing");
+        let mut homing = modifiers.homing.is_some();
+        if ui.checkbox(&mut homing, "").changed() {
+            modifiers.homing = homing.then(Homing::default);
+        }
+        ui.end_row();
+        ui.label("Split");
+        let mut split = modifiers.split.is_some();
+        if ui.checkbox(&mut split, "").changed() {
+            modifiers.split = split.then(Split::default);
+        }
+        ui.end_row();
+        ui.label("Damage type");
+        egui::ComboBox::from_id_salt(("damage_type", entity))
+            .selected_text(format!("{:?}", *damage_type))
+            .show_ui(ui, |ui| {
+                for option in [
+                    DamageType::Physical,
+                    DamageType::Fire,
+                    DamageType::Ice,
+                    DamageType::Poison,
+                    DamageType::Lightning,
+                ] {
+                    ui.selectable_value(&mut *damage_type, option, format!("{:?}", option));
+                }
+            });
+        ui.end_row();
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_console_commands(
+    mut commands: Commands,
+    mut command_events: EventReader<ConsoleCommand>,
+    mut console: ResMut<DebugConsole>,
+    mut god_mode: ResMut<GodMode>,
+    mut time_scale: ResMut<TimeScale>,
+    mut profile: ResMut<Profile>,
+    mut save_events: EventWriter<SaveScene>,
+    mut load_events: EventWriter<LoadScene>,
+    handle: Res<GlobalTextureAtlas>,
+    cursor_position: Res<CursorPosition>,
+    mut player_query: Query<(&Transform, PlayerComponents), With<Player>>,
+    mut enemy_query: Query<&mut Health, (With<Enemy>, Without<Player>)>,
+    bullet_query: Query<(), With<Bullet>>,
+    gun_query: Query<(), With<Gun>>,
+    mut gun_modifiers_query: Query<&mut ProjectileModifiers, (With<Gun>, Without<Player>)>,
+) {
+    for ConsoleCommand(line) in command_events.read() {
+        console.log.push(format!("> {}", line));
+        let command = match line.parse::<DebugCommand>() {
+            Ok(command) => command,
+            Err(error) => {
+                console.log.push(error);
+                continue;
+            }
+        };
+
+        let mut player = player_query.get_single_mut().ok();
+        let output = match command {
+            DebugCommand::Help => HELP.to_string(),
+            DebugCommand::Count => format!(
+                "{} enemies, {} bullets, {} guns",
+                enemy_query.iter().count(),
+                bullet_query.iter().count(),
+                gun_query.iter().count()
+            ),
+            DebugCommand::Spawn { enemy_type, count } => {
+                let position = cursor_position
+                    .value
+                    .or_else(|| {
+                        player
+                            .as_ref()
+                            .map(|(transform, _)| transform.translation.truncate())
+                    })
+                    .unwrap_or_default();
+                for _ in 0..count {
+                    spawn_enemy(&mut commands, &handle, enemy_type, position);
+                }
+                format!("spawned {} {} at {:.0}", count, enemy_type.name(), position)
+            }
+            DebugCommand::Xp(amount) => match player.as_mut() {
+                Some((_, components)) => {
+                    components.4.value += amount;
+                    format!("+{} XP", amount)
+                }
+                None => "no player".to_string(),
+            },
+            DebugCommand::Gold(amount) => {
+                profile.add_gold(amount);
+                format!("+{} gold", amount)
+            }
+            DebugCommand::God(value) => {
+                god_mode.0 = value.unwrap_or(!god_mode.0);
+                format!("god mode {}", if god_mode.0 { "on" } else { "off" })
+            }
+            DebugCommand::TimeScale(speed) => {
+                time_scale.set(speed);
+                if time_scale.frozen {
+                    "time frozen".to_string()
+                } else {
+                    format!("time scale {}", time_scale.scale)
+                }
+            }
+            DebugCommand::Step => {
+                time_scale.step();
+                "stepped one tick".to_string()
+            }
+            DebugCommand::Heal => match player.as_mut() {
+                Some((_, components)) => {
+                    components.0.value = components.1.value;
+                    "healed".to_string()
+                }
+                None => "no player".to_string(),
+            },
+            DebugCommand::KillAll => {
+                let mut killed = 0;
+                for mut health in enemy_query.iter_mut() {
+                    health.value = 0.0;
+                    killed += 1;
+                }
+                format!("killed {} enemies", killed)
+            }
+            DebugCommand::Set { stat, value } => match player.as_mut() {
+                Some((_, components)) => {
+                    let output = set_player_stat(components, &stat, value);
+                    if MODIFIER_STATS.contains(&stat.as_str()) {
+                        for mut modifiers in gun_modifiers_query.iter_mut() {
+                            *modifiers = *components.7;
+                        }
+                    }
+                    output
+                }
+                None => "no player".to_string(),
+            },
+            DebugCommand::SaveScene(name) => match scene_path(&name) {
+                Ok(path) => {
+                    save_events.send(SaveScene(name));
+                    format!("saving {}", path)
+                }
+                Err(error) => error,
+            },
+            DebugCommand::LoadScene(name) => match scene_path(&name) {
+                Ok(path) => {
+                    load_events.send(LoadScene(name));
+                    format!("loading {}", path)
+                }
+                Err(error) => error,
+            },
+        };
+        console.log.push(output);
+    }
+}
+
+fn set_player_stat(player: &mut QueryItem<PlayerComponents>, stat: &str, value: f32) -> String {
+    let (health, max_health, speed, gun_bag, experience, bonuses, dash, modifiers) = player;
+    let (damage, attack_speed, range, pierces, crit_chance, crit_multiplier, knockback) = bonuses;
+    match stat {
+        "health" => health.value = value,
+        "max_health" => max_health.value = value,
+        "speed" => speed.value = value,
+        "guns" => gun_bag.size = (value as u32).min(gun_bag.capacity).max(1),
+        "capacity" => gun_bag.capacity = (value as u32).clamp(1, GUN_OFFSET.len() as u32),
+        "xp" => experience.value = value,
+        "damage" => damage.value = value,
+        "attack_speed" => attack_speed.value = value,
+        "range" => range.value = value,
+        "pierces" => pierces.value = value as u32,
+        "crit_chance" => crit_chance.value = value,
+        "crit_multiplier" => crit_multiplier.value = value,
+        "knockback" => knockback.value = value,
+        "dash_charges" => {
+            dash.max_charges = value as u32;
+            dash.charges = dash.charges.min(dash.max_charges);
+        }
+        "dash_cooldown" => dash.cooldown = value.max(0.0),
+        "homing" => modifiers.homing = (value > 0.0).then(Homing::default),
+        "bounces" => modifiers.bounces = value as u32,
+        "chains" => modifiers.chains = value as u32,
+        "split" => {
+            modifiers.split = (value >= 1.0).then(|| Split {
+                fragments: value as u32,
+                ..default()
+            })
+        }
+        _ => {
+            return format!(
+                "unknown stat \"{}\", expected one of: {}",
+                stat, PLAYER_STATS
+            )
+        }
+    }
+    format!("{} = {}", stat, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<DebugCommand, String> {
+        line.parse()
+    }
+
+    #[test]
+    fn parses_commands_case_insensitively() {
+        assert_eq!(parse("help"), Ok(DebugCommand::Help));
+        assert_eq!(parse("  KillAll  "), Ok(DebugCommand::KillAll));
+        assert_eq!(parse("xp 12.5"), Ok(DebugCommand::Xp(12.5)));
+        assert_eq!(parse("god off"), Ok(DebugCommand::God(Some(false))));
+        assert_eq!(parse("god"), Ok(DebugCommand::God(None)));
+    }
+
+    #[test]
+    fn parses_spawn_with_optional_count() {
+        assert_eq!(
+            parse("spawn red"),
+            Ok(DebugCommand::Spawn {
+                enemy_type: EnemyType::Red,
+                count: 1
+            })
+        );
+        assert_eq!(
+            parse("spawn SKIN 4"),
+            Ok(DebugCommand::Spawn {
+                enemy_type: EnemyType::Skin,
+                count: 4
+            })
+        );
+    }
+
+    #[test]
+    fn parses_set_with_lowercase_stat() {
+        assert_eq!(
+            parse("set Max_Health 50"),
+            Ok(DebugCommand::Set {
+                stat: "max_health".to_string(),
+                value: 50.0
+            })
+        );
+    }
+
+    #[test]
+    fn clamps_negative_gold_and_time_scale() {
+        assert_eq!(parse("gold -5"), Ok(DebugCommand::Gold(0)));
+        assert_eq!(parse("timescale -1"), Ok(DebugCommand::TimeScale(0.0)));
+    }
+
+    #[test]
+    fn parses_scene_commands() {
+        assert_eq!(
+            parse("save arena"),
+            Ok(DebugCommand::SaveScene("arena".to_string()))
+        );
+        assert_eq!(
+            parse("LOAD arena"),
+            Ok(DebugCommand::LoadScene("arena".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        assert!(parse("").is_err());
+        assert!(parse("   ").is_err());
+        assert!(parse("fly").is_err());
+        assert!(parse("spawn").is_err());
+        assert!(parse("spawn dragon").is_err());
+        assert!(parse("spawn red many").is_err());
+        assert!(parse("xp").is_err());
+        assert!(parse("xp lots").is_err());
+        assert!(parse("god maybe").is_err());
+        assert!(parse("set").is_err());
+        assert!(parse("set health").is_err());
+        assert!(parse("save").is_err());
+    }
+}
@@ -1,20 +1,81 @@
 use std::f32::consts::PI;
-use std::time::Duration;
 
-use crate::components::Health;
+use crate::animation::AnimationPlayer;
+use crate::components::{DamageType, Health, Mass, MaxHealth};
 use crate::configs::*;
+use crate::events::{DamageSource, EnemyKilled};
+use crate::overlay::HealthBar;
 use crate::player::Player;
-use crate::{resources::GlobalTextureAtlas, state::GameState};
+use crate::resources::{GameRng, GlobalTextureAtlas};
+use crate::state::GameState;
+use crate::status::StatusEffects;
+use crate::timescale::{simulation_running, TimeScale};
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
 use bevy::math::vec3;
 use bevy::prelude::*;
-use bevy::time::common_conditions::on_timer;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 pub struct EnemyPlugin;
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Enemy;
-#[derive(Component)]
+
+/// The wave clock. Kept as a resource rather than a run condition so a saved run
+/// resumes partway through a wave.
+#[derive(Resource)]
+pub struct EnemySpawnTimer {
+    pub timer: Timer,
+}
+
+impl Default for EnemySpawnTimer {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(ENEMY_SPAWN_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+/// Knockback velocity that overrides the chase movement while `timer` runs.
+#[derive(Component, Default, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct HitStun {
+    pub velocity: Vec2,
+    pub timer: f32,
+}
+
+impl HitStun {
+    pub fn push(&mut self, direction: Vec2, force: f32, mass: f32) {
+        self.velocity += direction.normalize_or_zero() * force / mass.max(f32::EPSILON);
+        self.timer = HIT_STUN_DURATION;
+    }
+}
+
+/// Whatever dealt the most recent damage, reported as the killer on death.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct LastDamageSource {
+    pub value: Option<DamageSource>,
+}
+
+impl MapEntities for LastDamageSource {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.value = self.value.map(|source| match source {
+            DamageSource::Bullet(entity) => DamageSource::Bullet(entity_mapper.map_entity(entity)),
+            DamageSource::Contact(entity) => {
+                DamageSource::Contact(entity_mapper.map_entity(entity))
+            }
+            DamageSource::Ability(entity) => {
+                DamageSource::Ability(entity_mapper.map_entity(entity))
+            }
+            DamageSource::Status(kind) => DamageSource::Status(kind),
+        });
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
 pub enum EnemyType {
     Green,
     Red,
@@ -22,45 +83,94 @@ pub enum EnemyType {
 }
 
 impl EnemyType {
-    fn get_rand_enemy() -> Self {
-        let mut rng = rand::thread_rng();
+    pub const ALL: [EnemyType; 3] = [EnemyType::Green, EnemyType::Red, EnemyType::Skin];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyType::Green => "green",
+            EnemyType::Red => "red",
+            EnemyType::Skin => "skin",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|enemy_type| enemy_type.name().eq_ignore_ascii_case(name))
+    }
+
+    fn get_rand_enemy(rng: &mut impl Rng) -> Self {
         let rand_index = rng.gen_range(0..3);
-        return match rand_index {
+        match rand_index {
             0 => Self::Green,
             1 => Self::Red,
             _ => Self::Skin,
-        };
+        }
+    }
+
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            EnemyType::Green => SPRITE_ENEMY_GREEN,
+            EnemyType::Red => SPRITE_ENEMY_RED,
+            EnemyType::Skin => SPRITE_ENEMY_SKIN,
+        }
+    }
+
+    pub fn xp_reward(&self) -> f32 {
+        match self {
+            EnemyType::Green => 1.0,
+            EnemyType::Red => 2.0,
+            EnemyType::Skin => 3.0,
+        }
     }
 
-    pub fn get_base_sprite_index(&self) -> usize {
+    pub fn mass(&self) -> f32 {
         match self {
-            EnemyType::Green => 8,
-            EnemyType::Red => 12,
-            EnemyType::Skin => 20,
+            EnemyType::Green => 1.0,
+            EnemyType::Red => 1.5,
+            EnemyType::Skin => 2.0,
+        }
+    }
+
+    /// Fraction of incoming damage of the given type that is resisted.
+    /// Negative values are weaknesses and increase the damage taken.
+    pub fn resistance(&self, damage_type: DamageType) -> f32 {
+        match (self, damage_type) {
+            (EnemyType::Green, DamageType::Poison) => 0.5,
+            (EnemyType::Green, DamageType::Fire) => -0.5,
+            (EnemyType::Red, DamageType::Fire) => 0.75,
+            (EnemyType::Red, DamageType::Ice) => -0.5,
+            (EnemyType::Skin, DamageType::Physical) => 0.25,
+            (EnemyType::Skin, DamageType::Lightning) => -0.5,
+            _ => 0.0,
         }
     }
 }
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.init_resource::<EnemySpawnTimer>().add_systems(
             Update,
-            (
-                spawn_enemies.run_if(on_timer(Duration::from_secs_f32(ENEMY_SPAWN_INTERVAL))),
-                update_enemy_transform,
-                despawn_enemies,
-            )
-                .run_if(in_state(GameState::InGame)),
+            (spawn_enemies, update_enemy_transform, despawn_enemies)
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
         );
     }
 }
 
 fn spawn_enemies(
     mut commands: Commands,
+    time: Res<Time>,
+    mut spawn_timer: ResMut<EnemySpawnTimer>,
     handle: Res<GlobalTextureAtlas>,
     player_query: Query<&Transform, With<Player>>,
     enemy_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    mut rng: ResMut<GameRng>,
 ) {
+    if !spawn_timer.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
     let num_enemies = enemy_query.iter().len();
     let enemy_spawn_count = (MAX_NUM_ENEMIES - num_enemies).min(SPAWN_RATE_PER_SECOND);
 
@@ -70,11 +180,26 @@ fn spawn_enemies(
 
     let player_position = player_query.single().translation.truncate();
     for _ in 0..enemy_spawn_count {
-        let position = get_random_position_around(player_position);
-        let enemy_type = EnemyType::get_rand_enemy();
-        commands.spawn((
+        let position = get_random_position_around(&mut rng.rng, player_position);
+        spawn_enemy(
+            &mut commands,
+            &handle,
+            EnemyType::get_rand_enemy(&mut rng.rng),
+            position,
+        );
+    }
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    handle: &GlobalTextureAtlas,
+    enemy_type: EnemyType,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn((
             SpriteBundle {
-                texture: handle.image.clone().unwrap(),
+                texture: handle.image.clone(),
                 transform: Transform {
                     translation: vec3(position.x, position.y, 1.0),
                     scale: Vec3::splat(SPRITE_SCALE_FACTOR),
@@ -82,18 +207,28 @@ fn spawn_enemies(
                 },
                 ..default()
             },
-            TextureAtlas {
-                layout: handle.layout.clone().unwrap(),
-                index: enemy_type.get_base_sprite_index(),
-            },
+            handle.texture_atlas(enemy_type.sprite_name()),
             Enemy,
-            Health { value: 100.0 },
-        ));
-    }
+            enemy_type,
+            AnimationPlayer::new(enemy_type.sprite_name()),
+            Health {
+                value: ENEMY_HEALTH,
+            },
+            MaxHealth {
+                value: ENEMY_HEALTH,
+            },
+            HealthBar::default(),
+            Mass {
+                value: enemy_type.mass(),
+            },
+            HitStun::default(),
+            LastDamageSource::default(),
+            StatusEffects::default(),
+        ))
+        .id()
 }
 
-fn get_random_position_around(player_position: Vec2) -> Vec2 {
-    let mut rng = rand::thread_rng();
+fn get_random_position_around(rng: &mut impl Rng, player_position: Vec2) -> Vec2 {
     let offset_x = (BACKGROUND_SIZE.x - BACKGROUND_OFFSET.x) / 2.0;
     let offset_y = (BACKGROUND_SIZE.y - BACKGROUND_OFFSET.y) / 2.0;
     let mut enemy_position = Vec2::ZERO;
@@ -108,32 +243,103 @@ fn get_random_position_around(player_position: Vec2) -> Vec2 {
     enemy_position
 }
 
+#[allow(clippy::type_complexity)]
 fn update_enemy_transform(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
     player_query: Query<&Transform, With<Player>>,
-    mut enemy_query: Query<(&mut Transform, &mut Sprite), (With<Enemy>, Without<Player>)>,
+    mut enemy_query: Query<
+        (&mut Transform, &mut Sprite, &StatusEffects, &mut HitStun),
+        (With<Enemy>, Without<Player>),
+    >,
 ) {
     let Ok(player_pos) = player_query.get_single() else {
         return;
     };
+    let factor = time_scale.factor();
+
+    for (mut transform, mut sprite, status, mut hit_stun) in enemy_query.iter_mut() {
+        if hit_stun.timer > 0.0 {
+            hit_stun.timer -= time.delta_seconds();
+            transform.translation += hit_stun.velocity.extend(0.0) * factor;
+            hit_stun.velocity *= KNOCKBACK_DAMPING.powf(factor);
+            continue;
+        }
+        hit_stun.velocity = Vec2::ZERO;
+
+        let speed_multiplier = status.speed_multiplier();
+        if speed_multiplier <= 0.0 {
+            continue;
+        }
 
-    for (mut transform, mut sprite) in enemy_query.iter_mut() {
         let dir = (player_pos.translation - transform.translation).normalize();
-        let velocity = dir * ENEMY_SPEED;
-        transform.translation += velocity;
+        let velocity = dir * ENEMY_SPEED * speed_multiplier;
+        transform.translation += velocity * factor;
 
         // Flip enemy sprite
         sprite.flip_x = velocity.x < 0.0;
     }
 }
 
-fn despawn_enemies(mut commands: Commands, enemy_query: Query<(Entity, &Health), With<Enemy>>) {
+fn despawn_enemies(
+    mut commands: Commands,
+    enemy_query: Query<(Entity, &Transform, &Health, &EnemyType, &LastDamageSource), With<Enemy>>,
+    mut killed_events: EventWriter<EnemyKilled>,
+) {
     if enemy_query.is_empty() {
         return;
     }
 
-    for (entity, health) in enemy_query.iter() {
+    for (entity, transform, health, enemy_type, last_source) in enemy_query.iter() {
         if health.value <= 0.0 {
-            commands.entity(entity).despawn();
+            killed_events.send(EnemyKilled {
+                enemy: entity,
+                enemy_type: *enemy_type,
+                position: transform.translation.truncate(),
+                source: last_source.value,
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAMAGE_TYPES: [DamageType; 5] = [
+        DamageType::Physical,
+        DamageType::Fire,
+        DamageType::Ice,
+        DamageType::Poison,
+        DamageType::Lightning,
+    ];
+
+    #[test]
+    fn every_enemy_has_a_resistance_and_a_weakness() {
+        for enemy_type in EnemyType::ALL {
+            let resistances: Vec<f32> = DAMAGE_TYPES
+                .into_iter()
+                .map(|damage_type| enemy_type.resistance(damage_type))
+                .collect();
+            assert!(resistances.iter().any(|&value| value > 0.0));
+            assert!(resistances.iter().any(|&value| value < 0.0));
+        }
+    }
+
+    #[test]
+    fn resistances_never_grant_immunity() {
+        for enemy_type in EnemyType::ALL {
+            for damage_type in DAMAGE_TYPES {
+                assert!(enemy_type.resistance(damage_type) < 1.0);
+            }
         }
     }
+
+    #[test]
+    fn unlisted_pairs_take_full_damage() {
+        assert_eq!(EnemyType::Green.resistance(DamageType::Physical), 0.0);
+        assert_eq!(EnemyType::Red.resistance(DamageType::Poison), 0.0);
+        assert_eq!(EnemyType::Skin.resistance(DamageType::Fire), 0.0);
+    }
 }
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::{components::DamageType, enemy::EnemyType, status::StatusKind};
+
+pub struct EventsPlugin;
+
+/// What dealt a given amount of damage.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum DamageSource {
+    Bullet(Entity),
+    Status(StatusKind),
+    Contact(Entity),
+    /// An ability cast by the entity.
+    Ability(Entity),
+}
+
+/// Sent for every bullet that connects with an enemy.
+#[derive(Event)]
+pub struct BulletHit {
+    pub bullet: Entity,
+    pub enemy: Entity,
+    pub position: Vec2,
+    pub damage: f32,
+    pub damage_type: DamageType,
+    pub crit: bool,
+}
+
+/// Sent for every enemy an ability damages.
+#[derive(Event)]
+pub struct AbilityHit {
+    pub caster: Entity,
+    pub enemy: Entity,
+    pub position: Vec2,
+    pub damage: f32,
+    pub damage_type: DamageType,
+}
+
+/// Sent for every damage tick of a burn or poison.
+#[derive(Event)]
+pub struct StatusDamage {
+    pub enemy: Entity,
+    pub position: Vec2,
+    pub damage: f32,
+    pub kind: StatusKind,
+}
+
+/// Sent once when an enemy's health drops to zero, right before it is despawned.
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub enemy: Entity,
+    pub enemy_type: EnemyType,
+    pub position: Vec2,
+    pub source: Option<DamageSource>,
+}
+
+#[derive(Event)]
+pub struct PlayerDamaged {
+    pub player: Entity,
+    pub position: Vec2,
+    pub damage: f32,
+    pub source: DamageSource,
+}
+
+/// Sent once when the player's health drops to zero, right before the run is cleared.
+#[derive(Event)]
+pub struct PlayerKilled {
+    pub player: Entity,
+    pub position: Vec2,
+}
+
+#[derive(Event)]
+pub struct ShotFired {
+    pub gun: Entity,
+    pub bullet: Entity,
+    pub position: Vec2,
+    pub direction: Vec2,
+}
+
+#[derive(Event)]
+pub struct GunEquipped {
+    pub gun: Entity,
+    pub player: Entity,
+    pub slot: usize,
+}
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BulletHit>()
+            .add_event::<AbilityHit>()
+            .add_event::<StatusDamage>()
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerDamaged>()
+            .add_event::<PlayerKilled>()
+            .add_event::<ShotFired>()
+            .add_event::<GunEquipped>();
+    }
+}
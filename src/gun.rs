@@ -1,26 +1,94 @@
-use std::u32;
-
+use crate::animation::AnimationPlayer;
 use crate::components::{
-    AttackSpeed, AttackSpeedPercent, Damage, Direction, GunBag, NearestEnemy, Pierces, Position,
-    Range,
+    AttackSpeed, AttackSpeedPercent, CritChance, CritMultiplier, Damage, DamageType,
+    DamageVariance, Direction, GunBag, Knockback, NearestEnemy, Pierces, Position, Range,
 };
 use crate::configs::*;
 use crate::enemy::Enemy;
+use crate::events::{GunEquipped, ShotFired};
 use crate::player::Player;
-use crate::resources::GlobalTextureAtlas;
+use crate::prestige::GlobalMultipliers;
+use crate::projectile::ProjectileModifiers;
+use crate::resources::{GameRng, GlobalTextureAtlas};
 use crate::state::GameState;
+use crate::status::OnHitEffects;
+use crate::timescale::{simulation_running, TimeScale};
+use bevy::ecs::query::QueryData;
 use bevy::math::{vec2, vec3};
 use bevy::prelude::*;
 use bevy::time::Stopwatch;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 pub struct GunPlugin;
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Gun;
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Bullet;
 
+/// Stats a gun hands down to every bullet it fires.
+#[derive(Bundle, Clone, Serialize, Deserialize)]
+pub struct WeaponStatsBundle {
+    pub range: Range,
+    pub damage: Damage,
+    pub pierces: Pierces,
+    pub crit_chance: CritChance,
+    pub crit_multiplier: CritMultiplier,
+    pub damage_variance: DamageVariance,
+    pub damage_type: DamageType,
+    pub knockback: Knockback,
+    pub on_hit: OnHitEffects,
+    pub modifiers: ProjectileModifiers,
+}
+
+#[derive(QueryData)]
+pub struct WeaponStats {
+    pub range: &'static Range,
+    pub damage: &'static Damage,
+    pub pierces: &'static Pierces,
+    pub crit_chance: &'static CritChance,
+    pub crit_multiplier: &'static CritMultiplier,
+    pub damage_variance: &'static DamageVariance,
+    pub damage_type: &'static DamageType,
+    pub knockback: &'static Knockback,
+    pub on_hit: &'static OnHitEffects,
+    pub modifiers: &'static ProjectileModifiers,
+}
+
+impl WeaponStatsItem<'_> {
+    pub fn to_bundle(&self) -> WeaponStatsBundle {
+        WeaponStatsBundle {
+            range: Range {
+                value: self.range.value,
+            },
+            damage: Damage {
+                value: self.damage.value,
+            },
+            pierces: Pierces {
+                value: self.pierces.value,
+            },
+            crit_chance: CritChance {
+                value: self.crit_chance.value,
+            },
+            crit_multiplier: CritMultiplier {
+                value: self.crit_multiplier.value,
+            },
+            damage_variance: DamageVariance {
+                value: self.damage_variance.value,
+            },
+            damage_type: *self.damage_type,
+            knockback: Knockback {
+                value: self.knockback.value,
+            },
+            on_hit: self.on_hit.clone(),
+            modifiers: *self.modifiers,
+        }
+    }
+}
+
 impl Plugin for GunPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
@@ -32,62 +100,119 @@ impl Plugin for GunPlugin {
                 update_bullets,
                 despawn_bullets,
             )
-                .run_if(in_state(GameState::InGame)),
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
         );
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn spawn_gun(
     mut commands: Commands,
     handle: Res<GlobalTextureAtlas>,
-    player_query: Query<(&AttackSpeedPercent, &Damage, &Range, &Pierces, &GunBag), With<Player>>,
+    player_query: Query<
+        (
+            Entity,
+            &AttackSpeedPercent,
+            &Damage,
+            &Range,
+            &Pierces,
+            &CritChance,
+            &CritMultiplier,
+            &Knockback,
+            &OnHitEffects,
+            &ProjectileModifiers,
+            &GlobalMultipliers,
+            &GunBag,
+        ),
+        With<Player>,
+    >,
     gun_query: Query<&Transform, With<Gun>>,
+    mut equipped_events: EventWriter<GunEquipped>,
 ) {
     if player_query.is_empty() {
         return;
     }
 
     let num_guns = gun_query.iter().len() as u32;
-    let (attack_speed, damage, range, pierces, gun_bag) = player_query.single();
+    let (
+        player,
+        attack_speed,
+        damage,
+        range,
+        pierces,
+        crit_chance,
+        crit_multiplier,
+        knockback,
+        on_hit,
+        modifiers,
+        multipliers,
+        gun_bag,
+    ) = player_query.single();
 
     if gun_bag.size <= gun_bag.capacity && num_guns < gun_bag.size {
-        let denominator = 100.0;
-        let numerator = BULLET_SPAWN_INTERVAL_DEFAULT * denominator;
-        let mut attack_speed_interval = attack_speed.value;
-        if attack_speed_interval >= 0.0 {
-            attack_speed_interval = numerator / (denominator + attack_speed_interval);
-        } else {
-            attack_speed_interval = (numerator - attack_speed_interval / numerator) / denominator;
-        }
+        let attack_speed_interval =
+            resolve_attack_interval(attack_speed.value, multipliers.attack_speed);
+        let damage_type = GUN_DAMAGE_TYPES[num_guns as usize % GUN_DAMAGE_TYPES.len()];
 
-        commands.spawn((
-            SpriteBundle {
-                texture: handle.image.clone().unwrap(),
-                transform: Transform::from_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-                ..default()
-            },
-            TextureAtlas {
-                layout: handle.layout.clone().unwrap(),
-                index: 17,
-            },
-            Gun,
+        let gun = spawn_gun_entity(
+            &mut commands,
+            &handle,
             AttackSpeed {
                 timer: Stopwatch::new(),
                 interval: attack_speed_interval,
             },
-            Range {
-                value: BULLET_RANGE + range.value,
-            },
-            Damage {
-                value: BULLET_DAMAGE + damage.value,
-            },
-            Pierces {
-                value: BULLET_PIERCES + pierces.value,
+            WeaponStatsBundle {
+                range: Range {
+                    value: BULLET_RANGE + range.value,
+                },
+                damage: Damage {
+                    value: (BULLET_DAMAGE + damage.value) * multipliers.damage,
+                },
+                pierces: Pierces {
+                    value: BULLET_PIERCES + pierces.value,
+                },
+                crit_chance: CritChance {
+                    value: CRIT_CHANCE + crit_chance.value,
+                },
+                crit_multiplier: CritMultiplier {
+                    value: CRIT_MULTIPLIER + crit_multiplier.value,
+                },
+                damage_variance: DamageVariance {
+                    value: DAMAGE_VARIANCE,
+                },
+                damage_type,
+                knockback: Knockback {
+                    value: BULLET_KNOCKBACK + knockback.value,
+                },
+                on_hit: on_hit.with_damage_type(damage_type),
+                modifiers: *modifiers,
             },
-        ));
+        );
+
+        equipped_events.send(GunEquipped {
+            gun,
+            player,
+            slot: num_guns as usize,
+        });
     }
 }
 
+/// Turns a bonus attack speed percentage into the interval between shots.
+/// The global multiplier then divides the interval, so x2 fires twice as often.
+pub fn resolve_attack_interval(attack_speed_percent: f32, multiplier: f32) -> f32 {
+    let denominator = 100.0;
+    let numerator = BULLET_SPAWN_INTERVAL_DEFAULT * denominator;
+    let interval = if attack_speed_percent >= 0.0 {
+        numerator / (denominator + attack_speed_percent)
+    } else {
+        (numerator - attack_speed_percent / numerator) / denominator
+    };
+
+    interval / multiplier.max(f32::EPSILON)
+}
+
+#[allow(clippy::type_complexity)]
 fn update_gun_transform(
     player_query: Query<(&Transform, &NearestEnemy), (With<Player>, Without<Gun>, Without<Enemy>)>,
     enemy_query: Query<&Transform, (With<Enemy>, Without<Player>, Without<Gun>)>,
@@ -104,20 +229,20 @@ fn update_gun_transform(
     let player_position = player_transform.translation.truncate();
 
     for (index, (mut transform, mut sprite, range)) in gun_query.iter_mut().enumerate() {
+        // Guns past the last slot, e.g. from an old snapshot, stay where they are
+        let Some(&slot_offset) = GUN_OFFSET.get(index) else {
+            continue;
+        };
         let default_rotation = Quat::from_rotation_z(0.0);
-        let default_gun_position = player_position + GUN_OFFSET[index];
+        let default_gun_position = player_position + slot_offset;
 
         if let Some(enemy_entity) = nearest_enemy.entity {
             if let Ok(enemy_transform) = enemy_query.get(enemy_entity) {
                 if range.value >= nearest_enemy.distance {
                     let enemy_position = enemy_transform.translation.truncate();
                     let offset = 20.0;
-                    let gun_offset = if GUN_OFFSET[index].x > 0.0 {
-                        -offset
-                    } else {
-                        offset
-                    };
-                    let gun_position = player_position + GUN_OFFSET[index] + vec2(gun_offset, 0.0);
+                    let gun_offset = if slot_offset.x > 0.0 { -offset } else { offset };
+                    let gun_position = player_position + slot_offset + vec2(gun_offset, 0.0);
                     let angle = (enemy_position.y - gun_position.y)
                         .atan2(enemy_position.x - gun_position.x);
                     let new_gun_pos = vec2(
@@ -144,14 +269,16 @@ fn update_gun_transform(
 fn spawn_bullet(
     mut commands: Commands,
     time: Res<Time>,
-    mut gun_query: Query<(&Transform, &mut AttackSpeed, &Range, &Damage, &Pierces), With<Gun>>,
+    mut gun_query: Query<(Entity, &Transform, &mut AttackSpeed, WeaponStats), With<Gun>>,
     handle: Res<GlobalTextureAtlas>,
+    mut shot_events: EventWriter<ShotFired>,
+    mut rng: ResMut<GameRng>,
 ) {
     if gun_query.is_empty() {
         return;
     }
 
-    for (transform, mut attack_speed, range, damage, pierces) in gun_query.iter_mut() {
+    for (gun, transform, mut attack_speed, stats) in gun_query.iter_mut() {
         let gun_rotation = transform.rotation;
         if gun_rotation.z == 0.0 {
             break;
@@ -161,62 +288,96 @@ fn spawn_bullet(
 
         if attack_speed.timer.elapsed_secs() >= attack_speed.interval {
             attack_speed.timer.reset();
-            let mut rng = rand::thread_rng();
-
             for _ in 0..BULLETS_PER_SHOT {
                 let bullet_direction = transform.local_x();
                 let direction = vec3(
-                    bullet_direction.x + rng.gen_range(-0.5..0.5) * 0.0,
-                    bullet_direction.y + rng.gen_range(-0.5..0.5) * 0.0,
+                    bullet_direction.x + rng.rng.gen_range(-0.5..0.5) * 0.0,
+                    bullet_direction.y + rng.rng.gen_range(-0.5..0.5) * 0.0,
                     bullet_direction.z,
                 );
-                commands.spawn((
-                    SpriteBundle {
-                        texture: handle.image.clone().unwrap(),
-                        transform: Transform {
-                            translation: vec3(gun_position.x, gun_position.y, 1.0),
-                            rotation: gun_rotation,
-                            scale: Vec3::splat(SPRITE_SCALE_FACTOR),
-                        },
-                        ..default()
-                    },
-                    TextureAtlas {
-                        layout: handle.layout.clone().unwrap(),
-                        index: 16,
-                    },
-                    Bullet,
-                    Direction { value: direction },
-                    Range { value: range.value },
-                    Position {
-                        value: Vec2 {
-                            x: gun_position.x,
-                            y: gun_position.y,
-                        },
-                    },
-                    Damage {
-                        value: damage.value,
-                    },
-                    Pierces {
-                        value: pierces.value,
-                    },
-                ));
+                let bullet = fire_bullet(
+                    &mut commands,
+                    &handle,
+                    gun_position,
+                    direction,
+                    stats.to_bundle(),
+                );
+                shot_events.send(ShotFired {
+                    gun,
+                    bullet,
+                    position: gun_position,
+                    direction: direction.truncate(),
+                });
             }
         }
     }
 }
 
-fn update_bullets(mut bullet_query: Query<(&mut Transform, &Direction), With<Bullet>>) {
+pub fn spawn_gun_entity(
+    commands: &mut Commands,
+    handle: &GlobalTextureAtlas,
+    attack_speed: AttackSpeed,
+    stats: WeaponStatsBundle,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: handle.image.clone(),
+                transform: Transform::from_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
+                ..default()
+            },
+            handle.texture_atlas(SPRITE_GUN),
+            Gun,
+            AnimationPlayer::new(SPRITE_GUN),
+            attack_speed,
+            stats,
+        ))
+        .id()
+}
+
+pub fn fire_bullet(
+    commands: &mut Commands,
+    handle: &GlobalTextureAtlas,
+    origin: Vec2,
+    direction: Vec3,
+    stats: WeaponStatsBundle,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: handle.image.clone(),
+                transform: Transform {
+                    translation: vec3(origin.x, origin.y, 1.0),
+                    rotation: Quat::from_rotation_z(direction.y.atan2(direction.x)),
+                    scale: Vec3::splat(SPRITE_SCALE_FACTOR),
+                },
+                ..default()
+            },
+            handle.texture_atlas(SPRITE_BULLET),
+            Bullet,
+            Direction { value: direction },
+            Position { value: origin },
+            stats,
+        ))
+        .id()
+}
+
+pub fn update_bullets(
+    time_scale: Res<TimeScale>,
+    mut bullet_query: Query<(&mut Transform, &Direction), With<Bullet>>,
+) {
     if bullet_query.is_empty() {
         return;
     }
 
+    let step = BULLET_SPEED * time_scale.factor();
     for (mut transform, direction) in bullet_query.iter_mut() {
-        transform.translation += direction.value.normalize() * Vec3::splat(BULLET_SPEED);
+        transform.translation += direction.value.normalize() * Vec3::splat(step);
         transform.translation.z = 10.0;
     }
 }
 
-fn despawn_bullets(
+pub fn despawn_bullets(
     mut commands: Commands,
     mut bullet_query: Query<(Entity, &Transform, &Position, &Range, &mut Pierces), With<Bullet>>,
 ) {
@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResized};
+
+use crate::components::{AttackSpeedPercent, Damage, Health, MaxHealth, Pierces, Range};
+use crate::configs::*;
+use crate::dash::Dash;
+use crate::gun::resolve_attack_interval;
+use crate::pickup::Experience;
+use crate::player::Player;
+use crate::prestige::GlobalMultipliers;
+use crate::profile::Profile;
+use crate::resources::GlobalFont;
+use crate::state::GameState;
+use crate::stats::RunStats;
+use crate::timescale::TimeScale;
+
+pub struct HudPlugin;
+
+#[derive(Component)]
+struct HudRoot;
+
+/// A line of HUD text and the value it shows.
+#[derive(Component, Clone, Copy)]
+enum HudText {
+    Time,
+    /// Empty while the game runs at normal speed.
+    TimeScale,
+    Kills,
+    Gold,
+    Level,
+    Health,
+    Dash,
+    Damage,
+    AttackSpeed,
+    Range,
+    Pierces,
+}
+
+#[derive(Component, Clone, Copy)]
+enum HudBar {
+    Experience,
+    Health,
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Loading), spawn_hud)
+            .add_systems(Update, show_hud.run_if(state_changed::<GameState>))
+            .add_systems(Update, scale_hud)
+            .add_systems(
+                Update,
+                (update_hud_text, update_hud_bars).run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+fn spawn_hud(mut commands: Commands, font: Res<GlobalFont>) {
+    let style = TextStyle {
+        font: font.handle.clone(),
+        font_size: HUD_FONT_SIZE,
+        color: Color::WHITE,
+    };
+    let text = |field: HudText| (TextBundle::from_section("", style.clone()), field);
+    let bar = |field: HudBar, color: (u8, u8, u8)| {
+        (
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(HUD_BAR_SIZE.x),
+                    height: Val::Px(HUD_BAR_SIZE.y),
+                    ..default()
+                },
+                background_color: Color::srgb_u8(
+                    HUD_BAR_BACKGROUND_COLOR.0,
+                    HUD_BAR_BACKGROUND_COLOR.1,
+                    HUD_BAR_BACKGROUND_COLOR.2,
+                )
+                .into(),
+                ..default()
+            },
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                background_color: Color::srgb_u8(color.0, color.1, color.2).into(),
+                ..default()
+            },
+            field,
+        )
+    };
+    let column = |align: AlignItems| NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            align_items: align,
+            row_gap: Val::Px(HUD_MARGIN / 2.0),
+            ..default()
+        },
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(HUD_MARGIN)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HudRoot,
+        ))
+        .with_children(|root| {
+            root.spawn(column(AlignItems::Start)).with_children(|left| {
+                left.spawn(text(HudText::Health));
+                let (background, fill, field) = bar(HudBar::Health, HEALTH_BAR_COLOR);
+                left.spawn(background).with_children(|parent| {
+                    parent.spawn((fill, field));
+                });
+                left.spawn(text(HudText::Dash));
+                left.spawn(text(HudText::Level));
+                let (background, fill, field) = bar(HudBar::Experience, HUD_XP_BAR_COLOR);
+                left.spawn(background).with_children(|parent| {
+                    parent.spawn((fill, field));
+                });
+            });
+            root.spawn(column(AlignItems::Center))
+                .with_children(|center| {
+                    center.spawn(text(HudText::Time));
+                    center.spawn(text(HudText::Kills));
+                    center.spawn(text(HudText::Gold));
+                    center.spawn(text(HudText::TimeScale));
+                });
+            root.spawn(column(AlignItems::End)).with_children(|right| {
+                right.spawn(text(HudText::Damage));
+                right.spawn(text(HudText::AttackSpeed));
+                right.spawn(text(HudText::Range));
+                right.spawn(text(HudText::Pierces));
+            });
+        });
+}
+
+/// The HUD stays up while paused so the run can be inspected.
+fn show_hud(state: Res<State<GameState>>, mut root_query: Query<&mut Visibility, With<HudRoot>>) {
+    let visible = matches!(state.get(), GameState::InGame | GameState::Paused);
+    for mut visibility in root_query.iter_mut() {
+        *visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Keeps the HUD the same size relative to the window as at the default resolution.
+fn scale_hud(
+    mut resized_events: EventReader<WindowResized>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if resized_events.read().count() == 0 {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let scale = (window.width() / WINDOW_SIZE.x).min(window.height() / WINDOW_SIZE.y);
+    ui_scale.0 = scale.max(0.5);
+}
+
+#[allow(clippy::type_complexity)]
+fn update_hud_text(
+    run_stats: Res<RunStats>,
+    profile: Res<Profile>,
+    time_scale: Res<TimeScale>,
+    player_query: Query<
+        (
+            &Health,
+            &MaxHealth,
+            &Experience,
+            &Damage,
+            &AttackSpeedPercent,
+            &Range,
+            &Pierces,
+            &GlobalMultipliers,
+            &Dash,
+        ),
+        With<Player>,
+    >,
+    mut text_query: Query<(&mut Text, &HudText)>,
+) {
+    let Ok((
+        health,
+        max_health,
+        experience,
+        damage,
+        attack_speed,
+        range,
+        pierces,
+        multipliers,
+        dash,
+    )) = player_query.get_single()
+    else {
+        return;
+    };
+
+    for (mut text, field) in text_query.iter_mut() {
+        // Stats show what each gun ends up with, matching how guns are built in `spawn_gun`
+        let value = match field {
+            HudText::Time => {
+                let seconds = run_stats.elapsed as u64;
+                format!("{:02}:{:02}", seconds / 60, seconds % 60)
+            }
+            HudText::TimeScale if time_scale.frozen => "Frozen".to_string(),
+            HudText::TimeScale if time_scale.scale != 1.0 => format!("x{:.2}", time_scale.scale),
+            HudText::TimeScale => String::new(),
+            HudText::Kills => format!("Kills {}", run_stats.kills),
+            HudText::Gold => format!("Gold {}", profile.gold),
+            HudText::Level => format!("Level {}", experience.level().0),
+            HudText::Health => format!("HP {:.0}/{:.0}", health.value.max(0.0), max_health.value),
+            HudText::Dash => format!("Dash {}/{}", dash.charges, dash.max_charges),
+            HudText::Damage => format!(
+                "Damage {:.1}",
+                (BULLET_DAMAGE + damage.value) * multipliers.damage
+            ),
+            HudText::AttackSpeed => format!(
+                "Attack speed {:.2}/s",
+                1.0 / resolve_attack_interval(attack_speed.value, multipliers.attack_speed)
+            ),
+            HudText::Range => format!("Range {:.0}", BULLET_RANGE + range.value),
+            HudText::Pierces => format!("Pierces {}", BULLET_PIERCES + pierces.value),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+fn update_hud_bars(
+    player_query: Query<(&Health, &MaxHealth, &Experience), With<Player>>,
+    mut bar_query: Query<(&mut Style, &HudBar)>,
+) {
+    let Ok((health, max_health, experience)) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut style, bar) in bar_query.iter_mut() {
+        let ratio = match bar {
+            HudBar::Health => health.value / max_health.value.max(f32::EPSILON),
+            HudBar::Experience => {
+                let (_, progress, needed) = experience.level();
+                progress / needed
+            }
+        };
+        style.width = Val::Percent(ratio.clamp(0.0, 1.0) * 100.0);
+    }
+}
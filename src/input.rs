@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use kdtree::distance::squared_euclidean;
+
+use crate::bindings::{Action, ActionInput};
+use crate::collision::EnemyKdTree;
+use crate::configs::*;
+use crate::enemy::Enemy;
+use crate::pickup::XpOrb;
+use crate::player::{update_player_movement, MovementMode, Player};
+use crate::resources::CursorPosition;
+use crate::state::GameState;
+use crate::timescale::simulation_running;
+
+pub struct MovementInputPlugin;
+
+/// Where the player wants to go this frame, whatever the movement scheme.
+/// Up to unit length, shorter for a half-tilted stick.
+#[derive(Resource, Default)]
+pub struct MovementInput {
+    pub direction: Vec2,
+    /// A point the player faces even while standing still, the cursor in cursor mode.
+    pub facing: Option<Vec2>,
+}
+
+impl Plugin for MovementInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MovementInput>().add_systems(
+            Update,
+            read_movement_input
+                .before(update_player_movement)
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn read_movement_input(
+    mut contexts: EguiContexts,
+    movement_mode: Res<MovementMode>,
+    actions: ActionInput,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    cursor_position: Res<CursorPosition>,
+    enemy_kdtree: Res<EnemyKdTree>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    orb_query: Query<&Transform, (With<XpOrb>, Without<Player>)>,
+    player_query: Query<&Transform, With<Player>>,
+    mut movement: ResMut<MovementInput>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        movement.direction = Vec2::ZERO;
+        movement.facing = None;
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    movement.facing = match *movement_mode {
+        MovementMode::Cursor => cursor_position.value,
+        _ => None,
+    };
+    movement.direction = match *movement_mode {
+        MovementMode::Cursor => cursor_direction(player_position, cursor_position.value),
+        MovementMode::Keyboard if contexts.ctx_mut().wants_keyboard_input() => Vec2::ZERO,
+        MovementMode::Keyboard => keyboard_direction(&actions),
+        MovementMode::Gamepad => gamepad_direction(&gamepads, &axes, &buttons),
+        MovementMode::Autopilot => {
+            autopilot_direction(player_position, &enemy_kdtree, &enemy_query, &orb_query)
+        }
+    };
+}
+
+/// Heads for the cursor, stopping once close enough that the player would jitter around it.
+fn cursor_direction(player_position: Vec2, cursor_position: Option<Vec2>) -> Vec2 {
+    let Some(cursor_position) = cursor_position else {
+        return Vec2::ZERO;
+    };
+    if cursor_position.distance(player_position) <= MINIMUM_DISTANCE_CURSOR_PLAYER {
+        return Vec2::ZERO;
+    }
+    (cursor_position - player_position).normalize_or_zero()
+}
+
+/// The move actions, WASD and the arrow keys by default, normalized so diagonals aren't faster.
+fn keyboard_direction(actions: &ActionInput) -> Vec2 {
+    let axis = |negative: Action, positive: Action| {
+        actions.pressed(positive) as i8 as f32 - actions.pressed(negative) as i8 as f32
+    };
+    Vec2::new(
+        axis(Action::MoveLeft, Action::MoveRight),
+        axis(Action::MoveDown, Action::MoveUp),
+    )
+    .normalize_or_zero()
+}
+
+/// The left stick of the first gamepad that is pushed, falling back to its d-pad.
+/// Gamepads only show up when built with the `gamepad` feature.
+fn gamepad_direction(
+    gamepads: &Gamepads,
+    axes: &Axis<GamepadAxis>,
+    buttons: &ButtonInput<GamepadButton>,
+) -> Vec2 {
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        let stick = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        if stick.length() > GAMEPAD_STICK_DEADZONE {
+            return stick.clamp_length_max(1.0);
+        }
+
+        let pressed =
+            |button_type| buttons.pressed(GamepadButton::new(gamepad, button_type)) as i8 as f32;
+        let dpad = Vec2::new(
+            pressed(GamepadButtonType::DPadRight) - pressed(GamepadButtonType::DPadLeft),
+            pressed(GamepadButtonType::DPadUp) - pressed(GamepadButtonType::DPadDown),
+        );
+        if dpad != Vec2::ZERO {
+            return dpad.normalize();
+        }
+    }
+    Vec2::ZERO
+}
+
+/// Blends three steering forces: away from nearby enemies (weighted by how close
+/// they are), toward the nearest XP orb, and back from the arena edges.
+fn autopilot_direction(
+    player_position: Vec2,
+    enemy_kdtree: &EnemyKdTree,
+    enemy_query: &Query<&Transform, (With<Enemy>, Without<Player>)>,
+    orb_query: &Query<&Transform, (With<XpOrb>, Without<Player>)>,
+) -> Vec2 {
+    let mut flee = Vec2::ZERO;
+    if let Ok(threats) = enemy_kdtree.tree.within(
+        &[player_position.x, player_position.y],
+        AUTOPILOT_DANGER_RADIUS * AUTOPILOT_DANGER_RADIUS,
+        &squared_euclidean,
+    ) {
+        for (distance, &enemy) in threats.iter() {
+            let Ok(enemy_transform) = enemy_query.get(enemy) else {
+                continue;
+            };
+            let away = player_position - enemy_transform.translation.truncate();
+            let closeness = 1.0 - distance.sqrt() / AUTOPILOT_DANGER_RADIUS;
+            flee += away.normalize_or_zero() * closeness;
+        }
+    }
+
+    let seek = orb_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .min_by(|a, b| {
+            a.distance_squared(player_position)
+                .total_cmp(&b.distance_squared(player_position))
+        })
+        .map(|orb| (orb - player_position).normalize_or_zero())
+        .unwrap_or(Vec2::ZERO);
+
+    let extents = (BACKGROUND_SIZE - BACKGROUND_OFFSET) / 2.0;
+    let inner = extents - Vec2::splat(AUTOPILOT_WALL_MARGIN);
+    let overshoot = player_position.abs() - inner;
+    let wall = -player_position.signum() * (overshoot / AUTOPILOT_WALL_MARGIN).max(Vec2::ZERO);
+
+    (flee.clamp_length_max(1.0) * AUTOPILOT_FLEE_WEIGHT + seek * AUTOPILOT_PICKUP_WEIGHT + wall)
+        .normalize_or_zero()
+}
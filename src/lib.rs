@@ -1,9 +1,32 @@
+pub mod ability;
+pub mod animation;
+pub mod atlas;
+pub mod bindings;
 pub mod camera;
 pub mod collision;
+pub mod combat;
 pub mod components;
 pub mod configs;
+pub mod dash;
+pub mod debug;
 pub mod enemy;
+pub mod events;
 pub mod gun;
+pub mod hud;
+pub mod input;
+pub mod menu;
+pub mod offline;
+pub mod overlay;
+pub mod pickup;
 pub mod player;
+pub mod prestige;
+pub mod profile;
+pub mod projectile;
 pub mod resources;
+pub mod scene;
+pub mod shop;
+pub mod snapshot;
 pub mod state;
+pub mod stats;
+pub mod status;
+pub mod timescale;
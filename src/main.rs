@@ -1,14 +1,35 @@
+use ability::AbilityPlugin;
+use animation::AnimationPlugin;
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
+use bindings::BindingsPlugin;
 use camera::FollowCameraPlugin;
 use collision::CollisionPlugin;
 use configs::*;
+use dash::DashPlugin;
+use debug::DebugPlugin;
 use enemy::EnemyPlugin;
+use events::EventsPlugin;
 use gun::GunPlugin;
+use hud::HudPlugin;
+use input::MovementInputPlugin;
+use menu::MenuPlugin;
+use offline::OfflinePlugin;
+use overlay::OverlayPlugin;
+use pickup::PickupPlugin;
 use player::PlayerPlugin;
+use prestige::PrestigePlugin;
+use profile::ProfilePlugin;
+use projectile::ProjectilePlugin;
 use resources::ResourcesPlugin;
+use scene::GameScenePlugin;
 use shooter_rs::state::GameState;
 use shooter_rs::*;
+use shop::ShopPlugin;
+use snapshot::SnapshotPlugin;
+use stats::StatsPlugin;
+use status::StatusPlugin;
+use timescale::TimeScalePlugin;
 
 fn main() {
     App::new()
@@ -23,6 +44,8 @@ fn main() {
                         resolution: WINDOW_SIZE.into(),
                         ..default()
                     }),
+                    // Closing goes through the quit confirmation instead
+                    close_when_requested: false,
                     ..default()
                 }),
             EguiPlugin,
@@ -33,12 +56,33 @@ fn main() {
         //     BACKGROUND_COLOR.1,
         //     BACKGROUND_COLOR.2,
         // )))
+        .add_plugins(EventsPlugin)
         .add_plugins(FollowCameraPlugin)
         .add_plugins(ResourcesPlugin)
+        .add_plugins(MenuPlugin)
+        .add_plugins(BindingsPlugin)
         .add_plugins(PlayerPlugin)
+        .add_plugins(MovementInputPlugin)
+        .add_plugins(DashPlugin)
+        .add_plugins(AbilityPlugin)
         .add_plugins(GunPlugin)
         .add_plugins(EnemyPlugin)
+        .add_plugins(AnimationPlugin)
+        .add_plugins(OverlayPlugin)
+        .add_plugins(HudPlugin)
+        .add_plugins(DebugPlugin)
+        .add_plugins(PickupPlugin)
         .add_plugins(CollisionPlugin)
+        .add_plugins(ProfilePlugin)
+        .add_plugins(OfflinePlugin)
+        .add_plugins(SnapshotPlugin)
+        .add_plugins(GameScenePlugin)
+        .add_plugins(ShopPlugin)
+        .add_plugins(PrestigePlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(ProjectilePlugin)
+        .add_plugins(StatusPlugin)
+        .add_plugins(TimeScalePlugin)
         .insert_resource(Msaa::Off)
         .run();
 }
@@ -0,0 +1,199 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::bindings::{Action, ActionInput, BindingsOpen};
+use crate::overlay::OverlaySettings;
+use crate::player::{despawn_run, MovementMode, Player, RunEntityFilter};
+use crate::shop::ShopOpen;
+use crate::state::GameState;
+
+pub struct MenuPlugin;
+
+/// The screen to go back to when the settings screen is closed.
+#[derive(Resource)]
+pub struct SettingsOrigin(pub GameState);
+
+/// Set while the quit confirmation is showing.
+#[derive(Resource, Default)]
+pub struct QuitRequested(pub bool);
+
+impl Default for SettingsOrigin {
+    fn default() -> Self {
+        Self(GameState::MainMenu)
+    }
+}
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsOrigin>()
+            .init_resource::<QuitRequested>()
+            .add_systems(Update, (handle_escape, request_quit_on_close))
+            .add_systems(Update, main_menu_ui.run_if(in_state(GameState::MainMenu)))
+            .add_systems(Update, pause_menu_ui.run_if(in_state(GameState::Paused)))
+            .add_systems(Update, settings_ui.run_if(in_state(GameState::Settings)))
+            .add_systems(
+                Update,
+                quit_confirmation_ui.run_if(|quit: Res<QuitRequested>| quit.0),
+            );
+    }
+}
+
+/// The pause action backs out of whatever is open: the quit prompt, settings, or the pause menu.
+fn handle_escape(
+    input: ActionInput,
+    state: Res<State<GameState>>,
+    settings_origin: Res<SettingsOrigin>,
+    mut quit: ResMut<QuitRequested>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(Action::Pause) {
+        return;
+    }
+
+    if quit.0 {
+        quit.0 = false;
+        return;
+    }
+
+    match state.get() {
+        GameState::InGame => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::InGame),
+        GameState::Settings => next_state.set(settings_origin.0),
+        _ => {}
+    }
+}
+
+fn request_quit_on_close(
+    mut close_events: EventReader<WindowCloseRequested>,
+    mut quit: ResMut<QuitRequested>,
+) {
+    if close_events.read().count() > 0 {
+        quit.0 = true;
+    }
+}
+
+fn main_menu_ui(
+    mut contexts: EguiContexts,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut settings_origin: ResMut<SettingsOrigin>,
+    mut shop_open: ResMut<ShopOpen>,
+    mut quit: ResMut<QuitRequested>,
+) {
+    egui::Window::new("Shooter")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Play").clicked() {
+                    next_state.set(GameState::GameInit);
+                }
+                if ui.button("Shop").clicked() {
+                    shop_open.0 = !shop_open.0;
+                }
+                if ui.button("Settings").clicked() {
+                    settings_origin.0 = GameState::MainMenu;
+                    next_state.set(GameState::Settings);
+                }
+                if ui.button("Quit").clicked() {
+                    quit.0 = true;
+                }
+            });
+        });
+}
+
+fn pause_menu_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    run_query: Query<Entity, RunEntityFilter>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut settings_origin: ResMut<SettingsOrigin>,
+    mut shop_open: ResMut<ShopOpen>,
+    mut quit: ResMut<QuitRequested>,
+) {
+    egui::Window::new("Paused")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Resume").clicked() {
+                    next_state.set(GameState::InGame);
+                }
+                if ui.button("Shop").clicked() {
+                    shop_open.0 = !shop_open.0;
+                }
+                if ui.button("Settings").clicked() {
+                    settings_origin.0 = GameState::Paused;
+                    next_state.set(GameState::Settings);
+                }
+                if ui.button("Abandon run").clicked() {
+                    despawn_run(&mut commands, &run_query);
+                    next_state.set(GameState::MainMenu);
+                }
+                if ui.button("Quit").clicked() {
+                    quit.0 = true;
+                }
+            });
+        });
+}
+
+fn settings_ui(
+    mut contexts: EguiContexts,
+    mut movement_mode: ResMut<MovementMode>,
+    mut overlays: ResMut<OverlaySettings>,
+    mut bindings_open: ResMut<BindingsOpen>,
+    settings_origin: Res<SettingsOrigin>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    egui::Window::new("Settings")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Movement:");
+                for mode in MovementMode::ALL {
+                    ui.radio_value(&mut *movement_mode, mode, mode.name());
+                }
+            });
+            ui.checkbox(&mut overlays.health_bars, "Health bars");
+            ui.checkbox(&mut overlays.damage_numbers, "Damage numbers");
+            if ui.button("Key bindings").clicked() {
+                bindings_open.0 = !bindings_open.0;
+            }
+            ui.separator();
+            if ui.button("Back").clicked() {
+                next_state.set(settings_origin.0);
+            }
+        });
+}
+
+fn quit_confirmation_ui(
+    mut contexts: EguiContexts,
+    mut quit: ResMut<QuitRequested>,
+    mut exit_events: EventWriter<AppExit>,
+    player_query: Query<(), With<Player>>,
+) {
+    egui::Window::new("Quit?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .order(egui::Order::Foreground)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Quit the game?");
+            if !player_query.is_empty() {
+                ui.label("Your run will be saved and can be resumed next time.");
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Quit").clicked() {
+                    exit_events.send(AppExit::Success);
+                }
+                if ui.button("Cancel").clicked() {
+                    quit.0 = false;
+                }
+            });
+        });
+}
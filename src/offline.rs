@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::configs::*;
+use crate::pickup::Experience;
+use crate::player::Player;
+use crate::prestige::PrestigeBonus;
+use crate::profile::{unix_now, Profile, WeaponSnapshot};
+use crate::state::GameState;
+
+pub struct OfflinePlugin;
+
+/// Rewards earned while the game was closed, waiting to be claimed.
+#[derive(Resource, Debug)]
+pub struct OfflineReward {
+    pub elapsed_secs: u64,
+    pub kills: u64,
+    pub gold: u64,
+    pub xp: f32,
+}
+
+impl Plugin for OfflinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, calculate_offline_reward)
+            .add_systems(
+                Update,
+                welcome_back_ui
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<OfflineReward>),
+            );
+    }
+}
+
+/// Estimated kills per second for a loadout, assuming every shot lands and every
+/// pierce finds a target, scaled down by `OFFLINE_EFFICIENCY`.
+pub fn estimate_kill_rate(weapons: &[WeaponSnapshot]) -> f32 {
+    let damage_per_second: f32 = weapons
+        .iter()
+        .filter(|weapon| weapon.interval > 0.0)
+        .map(|weapon| {
+            let expected_hit =
+                weapon.damage * (1.0 + weapon.crit_chance * (weapon.crit_multiplier - 1.0));
+            expected_hit * weapon.pierces.max(1) as f32 / weapon.interval
+        })
+        .sum();
+
+    damage_per_second / ENEMY_HEALTH * OFFLINE_EFFICIENCY
+}
+
+fn calculate_offline_reward(mut commands: Commands, profile: Res<Profile>) {
+    let Some(last_seen) = profile.last_seen else {
+        return;
+    };
+
+    let max_secs = (OFFLINE_MAX_HOURS * 3600.0) as u64;
+    let elapsed_secs = unix_now().saturating_sub(last_seen).min(max_secs);
+    if elapsed_secs < OFFLINE_MIN_SECONDS {
+        return;
+    }
+
+    let kills = (estimate_kill_rate(&profile.weapons) * elapsed_secs as f32) as u64;
+    if kills == 0 {
+        return;
+    }
+
+    let gold_multiplier = profile.prestige_multiplier(PrestigeBonus::GoldGain);
+    commands.insert_resource(OfflineReward {
+        elapsed_secs,
+        kills,
+        gold: (kills as f32 * GOLD_PER_KILL as f32 * gold_multiplier) as u64,
+        xp: kills as f32 * OFFLINE_XP_PER_KILL,
+    });
+}
+
+fn welcome_back_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    reward: Res<OfflineReward>,
+    mut profile: ResMut<Profile>,
+    mut player_query: Query<&mut Experience, With<Player>>,
+) {
+    let hours = reward.elapsed_secs / 3600;
+    let minutes = (reward.elapsed_secs % 3600) / 60;
+    let mut claimed = false;
+
+    egui::Window::new("Welcome back")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("You were away for {}h {}m.", hours, minutes));
+            ui.label(format!("Your guns defeated {} enemies.", reward.kills));
+            ui.separator();
+            ui.label(format!("Gold: +{}", reward.gold));
+            ui.label(format!("XP: +{:.0}", reward.xp));
+            claimed = ui.button("Claim").clicked();
+        });
+
+    if claimed {
+        profile.add_gold(reward.gold);
+        if let Ok(mut experience) = player_query.get_single_mut() {
+            experience.value += reward.xp;
+        }
+        commands.remove_resource::<OfflineReward>();
+    }
+}
@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Health, MaxHealth};
+use crate::configs::*;
+use crate::events::{AbilityHit, BulletHit, StatusDamage};
+use crate::resources::GlobalFont;
+use crate::state::GameState;
+use crate::status::StatusKind;
+use crate::timescale::simulation_running;
+
+pub struct OverlayPlugin;
+
+/// Which in-world overlays are drawn. Stored with the profile settings.
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub health_bars: bool,
+    pub damage_numbers: bool,
+}
+
+/// Draws a bar above the entity. Without `always_visible` it only shows once damaged.
+#[derive(Component, Default)]
+pub struct HealthBar {
+    pub always_visible: bool,
+}
+
+#[derive(Component)]
+struct HealthBarBackground;
+
+#[derive(Component)]
+struct HealthBarFill;
+
+/// A pooled text entity. Hidden and free for reuse once `age` passes its lifetime.
+#[derive(Component)]
+pub struct DamageNumber {
+    pub age: f32,
+    pub color: Color,
+}
+
+#[derive(Clone, Copy)]
+enum DamageStyle {
+    Normal,
+    Crit,
+    Status(StatusKind),
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            health_bars: true,
+            damage_numbers: true,
+        }
+    }
+}
+
+impl DamageStyle {
+    fn font_size(&self) -> f32 {
+        match self {
+            DamageStyle::Normal => DAMAGE_NUMBER_FONT_SIZE,
+            DamageStyle::Crit => DAMAGE_NUMBER_CRIT_FONT_SIZE,
+            DamageStyle::Status(_) => DAMAGE_NUMBER_STATUS_FONT_SIZE,
+        }
+    }
+
+    fn color(&self) -> Color {
+        let (r, g, b) = match self {
+            DamageStyle::Normal => DAMAGE_NUMBER_COLOR,
+            DamageStyle::Crit => DAMAGE_NUMBER_CRIT_COLOR,
+            DamageStyle::Status(StatusKind::Burn) => DAMAGE_NUMBER_BURN_COLOR,
+            DamageStyle::Status(StatusKind::Poison) => DAMAGE_NUMBER_POISON_COLOR,
+            DamageStyle::Status(_) => DAMAGE_NUMBER_COLOR,
+        };
+        Color::srgb_u8(r, g, b)
+    }
+
+    fn format(&self, damage: f32) -> String {
+        match self {
+            DamageStyle::Crit => format!("{:.0}!", damage),
+            _ => format!("{:.0}", damage),
+        }
+    }
+}
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverlaySettings>()
+            .add_systems(OnExit(GameState::Loading), spawn_damage_number_pool)
+            .add_systems(
+                Update,
+                (attach_health_bars, update_health_bars, show_damage_numbers)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            // Numbers already on screen finish fading after leaving the run, but hold
+            // still while the game is paused or frozen
+            .add_systems(
+                Update,
+                animate_damage_numbers
+                    .after(show_damage_numbers)
+                    .run_if(not(in_state(GameState::Paused)))
+                    .run_if(simulation_running),
+            );
+    }
+}
+
+fn attach_health_bars(mut commands: Commands, query: Query<Entity, Added<HealthBar>>) {
+    for entity in query.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgb_u8(
+                            HEALTH_BAR_BACKGROUND_COLOR.0,
+                            HEALTH_BAR_BACKGROUND_COLOR.1,
+                            HEALTH_BAR_BACKGROUND_COLOR.2,
+                        ),
+                        custom_size: Some(HEALTH_BAR_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, HEALTH_BAR_OFFSET, 1.0),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                HealthBarBackground,
+            ));
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgb_u8(
+                            HEALTH_BAR_COLOR.0,
+                            HEALTH_BAR_COLOR.1,
+                            HEALTH_BAR_COLOR.2,
+                        ),
+                        custom_size: Some(HEALTH_BAR_SIZE),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        -HEALTH_BAR_SIZE.x / 2.0,
+                        HEALTH_BAR_OFFSET,
+                        2.0,
+                    ),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                HealthBarFill,
+            ));
+        });
+    }
+}
+
+fn update_health_bars(
+    settings: Res<OverlaySettings>,
+    owner_query: Query<(&Health, &MaxHealth, &HealthBar, &Children)>,
+    mut background_query: Query<
+        &mut Visibility,
+        (With<HealthBarBackground>, Without<HealthBarFill>),
+    >,
+    mut fill_query: Query<(&mut Sprite, &mut Visibility), With<HealthBarFill>>,
+) {
+    for (health, max_health, health_bar, children) in owner_query.iter() {
+        let ratio = (health.value / max_health.value.max(f32::EPSILON)).clamp(0.0, 1.0);
+        let visible = settings.health_bars && (health_bar.always_visible || ratio < 1.0);
+        let visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+
+        for &child in children.iter() {
+            if let Ok(mut background) = background_query.get_mut(child) {
+                *background = visibility;
+            }
+            if let Ok((mut sprite, mut fill)) = fill_query.get_mut(child) {
+                sprite.custom_size = Some(Vec2::new(HEALTH_BAR_SIZE.x * ratio, HEALTH_BAR_SIZE.y));
+                *fill = visibility;
+            }
+        }
+    }
+}
+
+fn spawn_damage_number_pool(mut commands: Commands, font: Res<GlobalFont>) {
+    for _ in 0..DAMAGE_NUMBER_POOL_SIZE {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: font.handle.clone(),
+                        font_size: DAMAGE_NUMBER_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                ),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DamageNumber {
+                age: DAMAGE_NUMBER_LIFETIME,
+                color: Color::WHITE,
+            },
+        ));
+    }
+}
+
+fn show_damage_numbers(
+    settings: Res<OverlaySettings>,
+    mut hit_events: EventReader<BulletHit>,
+    mut ability_events: EventReader<AbilityHit>,
+    mut status_events: EventReader<StatusDamage>,
+    mut pool_query: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let hits = hit_events.read().map(|hit| {
+        let style = if hit.crit {
+            DamageStyle::Crit
+        } else {
+            DamageStyle::Normal
+        };
+        (hit.position, hit.damage, style)
+    });
+    let ability_hits = ability_events
+        .read()
+        .map(|hit| (hit.position, hit.damage, DamageStyle::Normal));
+    let ticks = status_events
+        .read()
+        .map(|tick| (tick.position, tick.damage, DamageStyle::Status(tick.kind)));
+    let numbers: Vec<_> = hits.chain(ability_hits).chain(ticks).collect();
+    if !settings.damage_numbers {
+        return;
+    }
+
+    for (position, damage, style) in numbers {
+        // Reuse a free number, or the oldest one when the pool is exhausted
+        let Some((mut number, mut text, mut transform, mut visibility)) = pool_query
+            .iter_mut()
+            .max_by(|(a, ..), (b, ..)| a.age.total_cmp(&b.age))
+        else {
+            return;
+        };
+
+        number.age = 0.0;
+        number.color = style.color();
+        text.sections[0].value = style.format(damage);
+        text.sections[0].style.font_size = style.font_size();
+        text.sections[0].style.color = number.color;
+        transform.translation = position.extend(20.0);
+        *visibility = Visibility::Visible;
+    }
+}
+
+/// `Time` already follows the time scale, so the numbers rise and fade with the game.
+fn animate_damage_numbers(
+    time: Res<Time>,
+    mut query: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (mut number, mut text, mut transform, mut visibility) in query.iter_mut() {
+        if number.age >= DAMAGE_NUMBER_LIFETIME {
+            continue;
+        }
+
+        number.age += delta;
+        if number.age >= DAMAGE_NUMBER_LIFETIME {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        transform.translation.y += DAMAGE_NUMBER_RISE_SPEED * delta;
+        let alpha = 1.0 - number.age / DAMAGE_NUMBER_LIFETIME;
+        text.sections[0].style.color = number.color.with_alpha(alpha);
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    configs::*,
+    events::EnemyKilled,
+    player::Player,
+    state::GameState,
+    timescale::{simulation_running, TimeScale},
+};
+
+pub struct PickupPlugin;
+
+#[derive(Component, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct XpOrb {
+    pub value: f32,
+}
+
+#[derive(Component, Default, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Experience {
+    pub value: f32,
+}
+
+impl Experience {
+    /// Current level, starting at 1, with the XP earned into it and the XP it takes.
+    pub fn level(&self) -> (u32, f32, f32) {
+        let mut level = 1;
+        let mut remaining = self.value;
+        let mut needed = LEVEL_BASE_XP;
+        while remaining >= needed {
+            remaining -= needed;
+            needed *= LEVEL_XP_GROWTH;
+            level += 1;
+        }
+        (level, remaining, needed)
+    }
+}
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (drop_xp_orbs, attract_xp_orbs, collect_xp_orbs)
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
+        );
+    }
+}
+
+fn drop_xp_orbs(mut commands: Commands, mut killed_events: EventReader<EnemyKilled>) {
+    for killed in killed_events.read() {
+        spawn_xp_orb(
+            &mut commands,
+            killed.position,
+            killed.enemy_type.xp_reward(),
+        );
+    }
+}
+
+pub fn spawn_xp_orb(commands: &mut Commands, position: Vec2, value: f32) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::srgb_u8(XP_ORB_COLOR.0, XP_ORB_COLOR.1, XP_ORB_COLOR.2),
+                    custom_size: Some(Vec2::splat(XP_ORB_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(position.x, position.y, 0.5),
+                ..default()
+            },
+            XpOrb { value },
+        ))
+        .id()
+}
+
+fn attract_xp_orbs(
+    time_scale: Res<TimeScale>,
+    player_query: Query<&Transform, With<Player>>,
+    mut orb_query: Query<&mut Transform, (With<XpOrb>, Without<Player>)>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    for mut transform in orb_query.iter_mut() {
+        let offset = player_position - transform.translation.truncate();
+        if offset.length() <= PICKUP_MAGNET_RADIUS {
+            let step = offset.normalize_or_zero() * PICKUP_MAGNET_SPEED * time_scale.factor();
+            transform.translation += step.extend(0.0);
+        }
+    }
+}
+
+fn collect_xp_orbs(
+    mut commands: Commands,
+    mut player_query: Query<(&Transform, &mut Experience), With<Player>>,
+    orb_query: Query<(Entity, &Transform, &XpOrb), Without<Player>>,
+) {
+    let Ok((player_transform, mut experience)) = player_query.get_single_mut() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    for (entity, transform, orb) in orb_query.iter() {
+        if transform.translation.truncate().distance(player_position) <= PICKUP_RADIUS {
+            experience.value += orb.value;
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,107 +1,318 @@
+use crate::ability::Summon;
+use crate::animation::AnimationPlayer;
 use crate::components::{
-    AttackSpeedPercent, Damage, GunBag, Health, NearestEnemy, Pierces, Range, Speed,
+    AttackSpeedPercent, CritChance, CritMultiplier, Damage, GunBag, Health, HurtCooldown,
+    Knockback, MaxHealth, NearestEnemy, Pierces, Range, Speed,
 };
 use crate::configs::*;
-use crate::resources::CursorPosition;
+use crate::dash::Dash;
+use crate::enemy::Enemy;
+use crate::events::PlayerKilled;
+use crate::gun::{Bullet, Gun};
+use crate::input::MovementInput;
+use crate::overlay::HealthBar;
+use crate::pickup::{Experience, XpOrb};
+use crate::prestige::GlobalMultipliers;
+use crate::profile::Profile;
+use crate::projectile::ProjectileModifiers;
+use crate::shop::PermanentUpgrade;
+use crate::status::OnHitEffects;
+use crate::timescale::{simulation_running, TimeScale};
+use bevy::ecs::query::QueryData;
 use bevy::{math::vec3, prelude::*};
-use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
 
 use crate::{resources::GlobalTextureAtlas, state::GameState};
 
 pub struct PlayerPlugin;
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Player;
 
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovementMode {
+    #[default]
+    Cursor,
+    /// WASD or the arrow keys.
+    Keyboard,
+    /// Left stick or d-pad of any connected gamepad.
+    Gamepad,
+    /// Kites away from enemy clusters and collects XP without any input.
+    Autopilot,
+}
+
+/// Matches every entity spawned during a run.
+pub type RunEntityFilter = Or<(
+    With<Player>,
+    With<Enemy>,
+    With<Gun>,
+    With<Bullet>,
+    With<XpOrb>,
+    With<Summon>,
+)>;
+
+/// Everything about the player that changes during a run.
+#[derive(Bundle, Clone, Serialize, Deserialize)]
+pub struct PlayerStatsBundle {
+    pub health: Health,
+    pub max_health: MaxHealth,
+    pub hurt_cooldown: HurtCooldown,
+    pub speed: Speed,
+    pub gun_bag: GunBag,
+    pub experience: Experience,
+    pub multipliers: GlobalMultipliers,
+    // Bonus stats added on top of every gun's base values
+    pub attack_speed: AttackSpeedPercent,
+    pub damage: Damage,
+    pub range: Range,
+    pub pierces: Pierces,
+    pub crit_chance: CritChance,
+    pub crit_multiplier: CritMultiplier,
+    pub knockback: Knockback,
+    pub on_hit: OnHitEffects,
+    pub modifiers: ProjectileModifiers,
+    pub dash: Dash,
+}
+
+#[derive(QueryData)]
+pub struct PlayerStats {
+    pub health: &'static Health,
+    pub max_health: &'static MaxHealth,
+    pub hurt_cooldown: &'static HurtCooldown,
+    pub speed: &'static Speed,
+    pub gun_bag: &'static GunBag,
+    pub experience: &'static Experience,
+    pub multipliers: &'static GlobalMultipliers,
+    pub attack_speed: &'static AttackSpeedPercent,
+    pub damage: &'static Damage,
+    pub range: &'static Range,
+    pub pierces: &'static Pierces,
+    pub crit_chance: &'static CritChance,
+    pub crit_multiplier: &'static CritMultiplier,
+    pub knockback: &'static Knockback,
+    pub on_hit: &'static OnHitEffects,
+    pub modifiers: &'static ProjectileModifiers,
+    pub dash: &'static Dash,
+}
+
+impl MovementMode {
+    #[cfg(feature = "gamepad")]
+    pub const ALL: [MovementMode; 4] = [
+        MovementMode::Cursor,
+        MovementMode::Keyboard,
+        MovementMode::Gamepad,
+        MovementMode::Autopilot,
+    ];
+    /// Gamepads are never detected without the `gamepad` feature, so the mode is hidden.
+    #[cfg(not(feature = "gamepad"))]
+    pub const ALL: [MovementMode; 3] = [
+        MovementMode::Cursor,
+        MovementMode::Keyboard,
+        MovementMode::Autopilot,
+    ];
+
+    /// Falls back to cursor-follow for a saved mode this build can't use.
+    pub fn supported(self) -> Self {
+        if Self::ALL.contains(&self) {
+            self
+        } else {
+            MovementMode::Cursor
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovementMode::Cursor => "Cursor",
+            MovementMode::Keyboard => "Keyboard",
+            MovementMode::Gamepad => "Gamepad",
+            MovementMode::Autopilot => "Autopilot",
+        }
+    }
+}
+
+impl PlayerStatsBundle {
+    /// Starting stats for a new run, with permanent upgrades and prestige applied.
+    pub fn from_profile(profile: &Profile) -> Self {
+        let max_health = PLAYER_BASE_HEALTH + profile.upgrade_bonus(PermanentUpgrade::MaxHealth);
+        let capacity = GUN_BAG_BASE_CAPACITY + profile.upgrade_level(PermanentUpgrade::GunCapacity);
+        let starting_guns = 1 + profile.upgrade_level(PermanentUpgrade::StartingGuns);
+
+        Self {
+            health: Health { value: max_health },
+            max_health: MaxHealth { value: max_health },
+            hurt_cooldown: HurtCooldown::default(),
+            speed: Speed {
+                value: PLAYER_BASE_SPEED + profile.upgrade_bonus(PermanentUpgrade::Speed),
+            },
+            gun_bag: GunBag {
+                size: starting_guns.min(capacity),
+                capacity,
+            },
+            experience: Experience::default(),
+            multipliers: profile.multipliers(),
+            attack_speed: AttackSpeedPercent {
+                value: profile.upgrade_bonus(PermanentUpgrade::AttackSpeed),
+            },
+            damage: Damage {
+                value: profile.upgrade_bonus(PermanentUpgrade::Damage),
+            },
+            range: Range {
+                value: profile.upgrade_bonus(PermanentUpgrade::Range),
+            },
+            pierces: Pierces { value: 0 },
+            crit_chance: CritChance { value: 0.0 },
+            crit_multiplier: CritMultiplier { value: 0.0 },
+            knockback: Knockback { value: 0.0 },
+            on_hit: OnHitEffects::default(),
+            modifiers: ProjectileModifiers::from_profile(profile),
+            dash: Dash::new(
+                DASH_BASE_CHARGES + profile.upgrade_level(PermanentUpgrade::DashCharges),
+                (DASH_BASE_COOLDOWN - profile.upgrade_bonus(PermanentUpgrade::DashCooldown))
+                    .max(DASH_MIN_COOLDOWN),
+            ),
+        }
+    }
+}
+
+impl PlayerStatsItem<'_> {
+    pub fn to_bundle(&self) -> PlayerStatsBundle {
+        PlayerStatsBundle {
+            health: self.health.clone(),
+            max_health: self.max_health.clone(),
+            hurt_cooldown: self.hurt_cooldown.clone(),
+            speed: self.speed.clone(),
+            gun_bag: self.gun_bag.clone(),
+            experience: self.experience.clone(),
+            multipliers: *self.multipliers,
+            attack_speed: self.attack_speed.clone(),
+            damage: self.damage.clone(),
+            range: self.range.clone(),
+            pierces: self.pierces.clone(),
+            crit_chance: self.crit_chance.clone(),
+            crit_multiplier: self.crit_multiplier.clone(),
+            knockback: self.knockback.clone(),
+            on_hit: self.on_hit.clone(),
+            modifiers: *self.modifiers,
+            dash: self.dash.clone(),
+        }
+    }
+}
+
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::GameInit), init_player)
+        app.init_resource::<MovementMode>()
+            .add_systems(OnEnter(GameState::GameInit), init_player)
             .add_systems(
                 Update,
-                update_player_movement.run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(Update, player_ui);
+                (update_player_movement, end_run_on_death)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(simulation_running),
+            );
     }
 }
 
 fn init_player(
     mut commands: Commands,
     handle: Res<GlobalTextureAtlas>,
+    profile: Res<Profile>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    commands.spawn((
-        SpriteBundle {
-            texture: handle.image.clone().unwrap(),
-            transform: Transform::from_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-            ..default()
-        },
-        TextureAtlas {
-            layout: handle.layout.clone().unwrap(),
-            index: 0,
-        },
-        Player,
-        Health { value: 100.0 },
-        Speed { value: 2.5 },
-        AttackSpeedPercent { value: 0.0 },
-        Damage { value: 0.0 },
-        Range { value: 0.0 },
-        Pierces { value: 0 },
-        GunBag {
-            size: 1,
-            capacity: 6,
-        },
-        NearestEnemy::default(),
-    ));
+    spawn_player(
+        &mut commands,
+        &handle,
+        Vec2::ZERO,
+        PlayerStatsBundle::from_profile(&profile),
+    );
 
     next_state.set(GameState::InGame);
 }
 
-fn update_player_movement(
-    cursor_position: Res<CursorPosition>,
+pub fn spawn_player(
+    commands: &mut Commands,
+    handle: &GlobalTextureAtlas,
+    position: Vec2,
+    stats: PlayerStatsBundle,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: handle.image.clone(),
+                transform: Transform {
+                    translation: position.extend(0.0),
+                    scale: Vec3::splat(SPRITE_SCALE_FACTOR),
+                    ..default()
+                },
+                ..default()
+            },
+            handle.texture_atlas(SPRITE_PLAYER),
+            Player,
+            AnimationPlayer::new(SPRITE_PLAYER),
+            HealthBar {
+                always_visible: true,
+            },
+            stats,
+            NearestEnemy::default(),
+        ))
+        .id()
+}
+
+/// Drops every entity that belongs to the current run.
+pub fn despawn_run(commands: &mut Commands, run_query: &Query<Entity, RunEntityFilter>) {
+    for entity in run_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn update_player_movement(
+    time_scale: Res<TimeScale>,
+    movement: Res<MovementInput>,
     mut player_query: Query<(&mut Transform, &mut Sprite, &Speed), With<Player>>,
 ) {
-    if player_query.is_empty() {
+    let Ok((mut player_transform, mut sprite, player_speed)) = player_query.get_single_mut() else {
         return;
-    }
-
-    let (mut player_transform, mut sprite, player_speed) = player_query.single_mut();
-    let player_position = player_transform.translation.truncate();
-    let cursor_position = match cursor_position.value {
-        Some(position) => position,
-        None => player_position,
     };
 
-    let distance = cursor_position.distance(player_position);
+    let direction = movement.direction;
+    if let Some(facing) = movement.facing {
+        sprite.flip_x = facing.x < player_transform.translation.x;
+    } else if direction.x != 0.0 {
+        sprite.flip_x = direction.x < 0.0;
+    }
+    if direction == Vec2::ZERO {
+        return;
+    }
 
-    if distance > MINIMUM_DISTANCE_CURSOR_PLAYER {
-        let direction = (cursor_position - player_position).normalize();
-        player_transform.translation += vec3(direction.x, direction.y, 0.0) * player_speed.value;
+    player_transform.translation +=
+        vec3(direction.x, direction.y, 0.0) * player_speed.value * time_scale.factor();
 
-        let extents = Vec3::from(((BACKGROUND_SIZE - BACKGROUND_OFFSET) / 2.0, 0.0));
-        player_transform.translation = player_transform.translation.min(extents).max(-extents);
-    }
+    player_transform.translation = clamp_to_arena(player_transform.translation);
+}
 
-    // Flip the player sprite
-    sprite.flip_x = cursor_position.x < player_position.x;
+/// Keeps a position inside the walls of the arena.
+pub fn clamp_to_arena(translation: Vec3) -> Vec3 {
+    let extents = Vec3::from(((BACKGROUND_SIZE - BACKGROUND_OFFSET) / 2.0, 0.0));
+    translation.min(extents).max(-extents)
 }
 
-fn player_ui(
-    mut contexts: EguiContexts,
-    mut player_query: Query<(&mut Speed, &mut GunBag), With<Player>>,
+/// Clears the arena and starts a new run once the player runs out of health.
+fn end_run_on_death(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Transform, &Health), With<Player>>,
+    run_query: Query<Entity, RunEntityFilter>,
+    mut killed_events: EventWriter<PlayerKilled>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if player_query.is_empty() {
+    let Ok((player, transform, health)) = player_query.get_single() else {
+        return;
+    };
+    if health.value > 0.0 {
         return;
     }
 
-    let (mut speed, mut gun_bag) = player_query.single_mut();
-
-    egui::Window::new("Player").show(contexts.ctx_mut(), |ui| {
-        ui.horizontal(|ui| {
-            ui.label("Speed:");
-            ui.add(egui::Slider::new(&mut speed.value, 0.0..=10.0).text("value"));
-        });
-        ui.horizontal(|ui| {
-            ui.label("Gun:");
-            ui.add(egui::Slider::new(&mut gun_bag.size, 1..=6).text("value"));
-        });
+    killed_events.send(PlayerKilled {
+        player,
+        position: transform.translation.truncate(),
     });
+    despawn_run(&mut commands, &run_query);
+    next_state.set(GameState::GameInit);
 }
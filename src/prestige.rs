@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::configs::*;
+use crate::player::{despawn_run, RunEntityFilter};
+use crate::profile::Profile;
+use crate::state::GameState;
+
+pub struct PrestigePlugin;
+
+/// Global multipliers bought with prestige points. They survive prestige resets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PrestigeBonus {
+    Damage,
+    AttackSpeed,
+    GoldGain,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PrestigeProgress {
+    pub points: u64,
+    pub resets: u32,
+    pub bonuses: BTreeMap<PrestigeBonus, u32>,
+}
+
+/// Multipliers resolved from the profile when a run starts.
+#[derive(Component, Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct GlobalMultipliers {
+    pub damage: f32,
+    pub attack_speed: f32,
+    pub gold_gain: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct PrestigeOpen(pub bool);
+
+impl PrestigeBonus {
+    pub const ALL: [PrestigeBonus; 3] = [
+        PrestigeBonus::Damage,
+        PrestigeBonus::AttackSpeed,
+        PrestigeBonus::GoldGain,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrestigeBonus::Damage => "Damage",
+            PrestigeBonus::AttackSpeed => "Attack speed",
+            PrestigeBonus::GoldGain => "Gold gain",
+        }
+    }
+
+    pub fn cost(&self, level: u32) -> u64 {
+        level as u64 + 1
+    }
+}
+
+impl Default for GlobalMultipliers {
+    fn default() -> Self {
+        Self {
+            damage: 1.0,
+            attack_speed: 1.0,
+            gold_gain: 1.0,
+        }
+    }
+}
+
+impl Profile {
+    pub fn prestige_level(&self, bonus: PrestigeBonus) -> u32 {
+        self.prestige
+            .bonuses
+            .get(&bonus)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn prestige_multiplier(&self, bonus: PrestigeBonus) -> f32 {
+        1.0 + self.prestige_level(bonus) as f32 * PRESTIGE_BONUS_STEP
+    }
+
+    pub fn multipliers(&self) -> GlobalMultipliers {
+        GlobalMultipliers {
+            damage: self.prestige_multiplier(PrestigeBonus::Damage),
+            attack_speed: self.prestige_multiplier(PrestigeBonus::AttackSpeed),
+            gold_gain: self.prestige_multiplier(PrestigeBonus::GoldGain),
+        }
+    }
+
+    /// Prestige points a reset would grant right now.
+    pub fn pending_prestige_points(&self) -> u64 {
+        (self.gold_earned as f64 / PRESTIGE_GOLD_DIVISOR as f64).sqrt() as u64
+    }
+
+    /// Wipes gold and permanent upgrades in exchange for prestige points.
+    pub fn prestige(&mut self) -> u64 {
+        let points = self.pending_prestige_points();
+        if points == 0 {
+            return 0;
+        }
+
+        self.gold = 0;
+        self.gold_earned = 0;
+        self.upgrades.clear();
+        self.prestige.points += points;
+        self.prestige.resets += 1;
+        points
+    }
+
+    pub fn buy_prestige_bonus(&mut self, bonus: PrestigeBonus) -> bool {
+        let level = self.prestige_level(bonus);
+        let cost = bonus.cost(level);
+        if self.prestige.points < cost {
+            return false;
+        }
+
+        self.prestige.points -= cost;
+        self.prestige.bonuses.insert(bonus, level + 1);
+        true
+    }
+}
+
+impl Plugin for PrestigePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PrestigeOpen>()
+            .add_systems(Update, prestige_ui);
+    }
+}
+
+fn prestige_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut prestige_open: ResMut<PrestigeOpen>,
+    mut profile: ResMut<Profile>,
+    run_query: Query<Entity, RunEntityFilter>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !prestige_open.0 {
+        return;
+    }
+
+    egui::Window::new("Prestige")
+        .open(&mut prestige_open.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Prestige points: {}", profile.prestige.points));
+            ui.label(format!("Resets: {}", profile.prestige.resets));
+            ui.separator();
+
+            egui::Grid::new("prestige_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for bonus in PrestigeBonus::ALL {
+                        let level = profile.prestige_level(bonus);
+                        let cost = bonus.cost(level);
+                        ui.label(bonus.name());
+                        ui.label(format!("x{:.2}", profile.prestige_multiplier(bonus)));
+                        let affordable = profile.prestige.points >= cost;
+                        let button = ui.add_enabled(
+                            affordable,
+                            egui::Button::new(format!("Buy ({} points)", cost)),
+                        );
+                        if button.clicked() {
+                            profile.buy_prestige_bonus(bonus);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            let pending = profile.pending_prestige_points();
+            if run_query.is_empty() {
+                ui.label("Resetting wipes gold and permanent upgrades.");
+            } else {
+                ui.label("Resetting wipes gold and permanent upgrades and ends the current run.");
+            }
+            let button = ui.add_enabled(
+                pending > 0,
+                egui::Button::new(format!("Prestige for {} points", pending)),
+            );
+            if button.clicked() {
+                profile.prestige();
+                // The live player still carries the wiped upgrades, so start over
+                if !run_query.is_empty() {
+                    despawn_run(&mut commands, &run_query);
+                    next_state.set(GameState::GameInit);
+                }
+            }
+        });
+}
@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::ActionMap;
+use crate::components::{AttackSpeed, CritChance, CritMultiplier, Damage, Pierces};
+use crate::configs::*;
+use crate::gun::Gun;
+use crate::overlay::OverlaySettings;
+use crate::player::MovementMode;
+use crate::prestige::PrestigeProgress;
+use crate::shop::PermanentUpgrade;
+use crate::state::GameState;
+use crate::stats::Statistics;
+
+pub struct ProfilePlugin;
+
+/// Bump when the file layout changes, and teach `Profile::parse` to migrate the old one.
+pub const PROFILE_VERSION: u32 = 2;
+
+/// The stats of one equipped gun, kept so progress can be estimated while the game is closed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeaponSnapshot {
+    pub damage: f32,
+    pub interval: f32,
+    pub pierces: u32,
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
+}
+
+/// Player data that outlives a single run.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub version: u32,
+    pub gold: u64,
+    /// Gold earned since the last prestige reset.
+    pub gold_earned: u64,
+    /// Unix timestamp in seconds of the last time the profile was saved.
+    pub last_seen: Option<u64>,
+    pub weapons: Vec<WeaponSnapshot>,
+    pub upgrades: BTreeMap<PermanentUpgrade, u32>,
+    pub prestige: PrestigeProgress,
+    pub unlocks: BTreeSet<String>,
+    pub settings: Settings,
+    /// Longest runs in seconds, best first.
+    pub best_times: Vec<f32>,
+    pub statistics: Statistics,
+    /// Set when the file on disk is from a newer version, so it is never overwritten.
+    #[serde(skip)]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub movement_mode: MovementMode,
+    pub overlays: OverlaySettings,
+    pub action_map: ActionMap,
+}
+
+/// Layout of the profile before the file carried a version number.
+#[derive(Deserialize)]
+struct ProfileV1 {
+    gold: u64,
+    #[serde(default)]
+    gold_earned: u64,
+    last_seen: Option<u64>,
+    weapons: Vec<WeaponSnapshot>,
+    #[serde(default)]
+    upgrades: BTreeMap<PermanentUpgrade, u32>,
+    #[serde(default)]
+    prestige: PrestigeProgress,
+}
+
+#[derive(Deserialize)]
+struct ProfileHeader {
+    #[serde(default = "ProfileHeader::unversioned")]
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    UnsupportedVersion(u32),
+}
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        let profile = Profile::load();
+        app.insert_resource(profile.settings.movement_mode.supported())
+            .insert_resource(profile.settings.overlays)
+            .insert_resource(profile.settings.action_map.clone().with_defaults())
+            .insert_resource(profile)
+            .add_systems(
+                Update,
+                store_settings.run_if(
+                    resource_changed::<MovementMode>
+                        .or_else(resource_changed::<OverlaySettings>)
+                        .or_else(resource_changed::<ActionMap>),
+                ),
+            )
+            .add_systems(
+                Update,
+                autosave_profile
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(on_timer(Duration::from_secs_f32(PROFILE_AUTOSAVE_INTERVAL))),
+            )
+            .add_systems(Last, save_profile_on_exit);
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+impl ProfileHeader {
+    fn unversioned() -> u32 {
+        1
+    }
+}
+
+impl From<ProfileV1> for Profile {
+    fn from(old: ProfileV1) -> Self {
+        Self {
+            gold: old.gold,
+            gold_earned: old.gold_earned,
+            last_seen: old.last_seen,
+            weapons: old.weapons,
+            upgrades: old.upgrades,
+            prestige: old.prestige,
+            ..default()
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            version: PROFILE_VERSION,
+            gold: 0,
+            gold_earned: 0,
+            last_seen: None,
+            weapons: Vec::new(),
+            upgrades: BTreeMap::new(),
+            prestige: PrestigeProgress::default(),
+            unlocks: BTreeSet::new(),
+            settings: Settings::default(),
+            best_times: Vec::new(),
+            statistics: Statistics::default(),
+            read_only: false,
+        }
+    }
+}
+
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::Io(error) => write!(f, "{}", error),
+            ProfileError::Parse(error) => write!(f, "{}", error),
+            ProfileError::UnsupportedVersion(version) => {
+                write!(f, "unsupported profile version {}", version)
+            }
+        }
+    }
+}
+
+impl Profile {
+    pub fn path() -> Option<PathBuf> {
+        data_path(PROFILE_FILE_NAME)
+    }
+
+    pub fn add_gold(&mut self, amount: u64) {
+        self.gold += amount;
+        self.gold_earned += amount;
+    }
+
+    /// Keeps the `PROFILE_BEST_TIMES` longest runs.
+    pub fn record_time(&mut self, secs: f32) {
+        self.best_times.push(secs);
+        self.best_times.sort_by(|a, b| b.total_cmp(a));
+        self.best_times.truncate(PROFILE_BEST_TIMES);
+    }
+
+    /// Parses a profile of any known version, migrating it to the current layout.
+    pub fn parse(contents: &str) -> Result<Self, ProfileError> {
+        let header: ProfileHeader = ron::from_str(contents).map_err(ProfileError::Parse)?;
+        let profile = match header.version {
+            1 => ron::from_str::<ProfileV1>(contents)
+                .map_err(ProfileError::Parse)?
+                .into(),
+            PROFILE_VERSION => ron::from_str::<Profile>(contents).map_err(ProfileError::Parse)?,
+            version => return Err(ProfileError::UnsupportedVersion(version)),
+        };
+
+        Ok(Profile {
+            version: PROFILE_VERSION,
+            ..profile
+        })
+    }
+
+    /// Loads the profile from disk. A missing file gives a fresh profile; an unreadable
+    /// one is moved aside as a backup before starting fresh, so nothing is overwritten.
+    /// A file from a newer version is left alone and the session's profile is never saved.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        let result = fs::read_to_string(&path)
+            .map_err(ProfileError::Io)
+            .and_then(|contents| Self::parse(&contents));
+
+        match result {
+            Ok(profile) => profile,
+            Err(ProfileError::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(error @ ProfileError::UnsupportedVersion(_)) => {
+                error!(
+                    "Could not load profile {}: {}. Progress will not be saved this session",
+                    path.display(),
+                    error
+                );
+                Self {
+                    read_only: true,
+                    ..default()
+                }
+            }
+            Err(error) => {
+                let backup = path.with_extension(format!("ron.corrupt-{}", unix_now()));
+                warn!(
+                    "Could not load profile {}: {}. Backing it up to {}",
+                    path.display(),
+                    error,
+                    backup.display()
+                );
+                if let Err(error) = fs::rename(&path, &backup) {
+                    warn!("Could not back up profile: {}", error);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        write_ron_atomic(&path, self)
+    }
+}
+
+/// Location of a file in the game's data directory.
+pub fn data_path(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(PROFILE_DIRECTORY).join(file_name))
+}
+
+/// Writes to a temporary file first and renames it over the target,
+/// so a crash mid-save never leaves a half-written file behind.
+pub fn write_ron_atomic<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    let temporary = path.with_extension("ron.tmp");
+    let mut file = fs::File::create(&temporary)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(temporary, path)
+}
+
+fn store_settings(
+    movement_mode: Res<MovementMode>,
+    overlays: Res<OverlaySettings>,
+    action_map: Res<ActionMap>,
+    mut profile: ResMut<Profile>,
+) {
+    profile.settings.movement_mode = *movement_mode;
+    profile.settings.overlays = *overlays;
+    if profile.settings.action_map != *action_map {
+        profile.settings.action_map = action_map.clone();
+    }
+}
+
+type GunStatsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static AttackSpeed,
+        &'static Damage,
+        &'static Pierces,
+        &'static CritChance,
+        &'static CritMultiplier,
+    ),
+    With<Gun>,
+>;
+
+fn record_weapons(profile: &mut Profile, gun_query: &GunStatsQuery) {
+    if gun_query.is_empty() {
+        return;
+    }
+
+    profile.weapons = gun_query
+        .iter()
+        .map(
+            |(attack_speed, damage, pierces, crit_chance, crit_multiplier)| WeaponSnapshot {
+                damage: damage.value,
+                interval: attack_speed.interval,
+                pierces: pierces.value,
+                crit_chance: crit_chance.value,
+                crit_multiplier: crit_multiplier.value,
+            },
+        )
+        .collect();
+}
+
+fn write_profile(profile: &mut Profile) {
+    profile.last_seen = Some(unix_now());
+    if let Err(error) = profile.save() {
+        warn!("Could not save profile: {}", error);
+    }
+}
+
+fn autosave_profile(mut profile: ResMut<Profile>, gun_query: GunStatsQuery) {
+    record_weapons(&mut profile, &gun_query);
+    write_profile(&mut profile);
+}
+
+pub fn save_profile_on_exit(
+    exit_events: EventReader<AppExit>,
+    mut profile: ResMut<Profile>,
+    gun_query: GunStatsQuery,
+) {
+    if exit_events.is_empty() {
+        return;
+    }
+
+    record_weapons(&mut profile, &gun_query);
+    write_profile(&mut profile);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_unversioned_file_as_v1() {
+        let contents = r#"(
+            gold: 120,
+            last_seen: Some(1700000000),
+            weapons: [(damage: 5.0, interval: 0.5, pierces: 1, crit_chance: 0.1, crit_multiplier: 1.5)],
+        )"#;
+
+        let profile = Profile::parse(contents).unwrap();
+        assert_eq!(profile.version, PROFILE_VERSION);
+        assert_eq!(profile.gold, 120);
+        assert_eq!(profile.last_seen, Some(1700000000));
+        assert_eq!(profile.weapons.len(), 1);
+        assert!(profile.best_times.is_empty());
+    }
+
+    #[test]
+    fn parses_current_version() {
+        let mut profile = Profile {
+            gold: 42,
+            ..default()
+        };
+        profile.record_time(90.0);
+        profile.statistics.runs = 3;
+        let contents = ron::to_string(&profile).unwrap();
+
+        let parsed = Profile::parse(&contents).unwrap();
+        assert_eq!(parsed.gold, 42);
+        assert_eq!(parsed.best_times, vec![90.0]);
+        assert_eq!(parsed.statistics.runs, 3);
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let contents = ron::to_string(&Profile::default()).unwrap();
+        let truncated = &contents[..contents.len() / 2];
+
+        assert!(matches!(
+            Profile::parse(truncated),
+            Err(ProfileError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        assert!(matches!(
+            Profile::parse("(version: 99, gold: 5)"),
+            Err(ProfileError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn write_ron_atomic_round_trips() {
+        let dir = std::env::temp_dir().join(format!("crate-profile-test-{}", std::process::id()));
+        let path = dir.join(PROFILE_FILE_NAME);
+        let profile = Profile {
+            gold: 7,
+            gold_earned: 11,
+            ..default()
+        };
+
+        write_ron_atomic(&path, &profile).unwrap();
+        let parsed = Profile::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        let leftover = path.with_extension("ron.tmp").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(parsed.gold, 7);
+        assert_eq!(parsed.gold_earned, 11);
+        assert!(!leftover);
+    }
+}
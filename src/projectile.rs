@@ -0,0 +1,277 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use kdtree::distance::squared_euclidean;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision::{handle_bullet_collision, EnemyKdTree},
+    components::{Direction, Pierces, Position},
+    configs::*,
+    enemy::Enemy,
+    events::BulletHit,
+    gun::{despawn_bullets, fire_bullet, update_bullets, Bullet, WeaponStats},
+    profile::Profile,
+    resources::GlobalTextureAtlas,
+    shop::PermanentUpgrade,
+    state::GameState,
+    timescale::{simulation_running, TimeScale},
+};
+
+pub struct ProjectilePlugin;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+pub struct Homing {
+    pub turn_rate: f32,
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+pub struct Split {
+    pub fragments: u32,
+    pub damage_fraction: f32,
+}
+
+/// Behaviours layered on top of straight-line bullet movement. Guns copy the
+/// player's modifiers on spawn, so upgrades to the player reach every new gun.
+#[derive(Component, Debug, Clone, Copy, Default, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct ProjectileModifiers {
+    pub homing: Option<Homing>,
+    pub bounces: u32,
+    pub chains: u32,
+    pub split: Option<Split>,
+}
+
+impl ProjectileModifiers {
+    /// The modifiers bought in the shop, which every gun of a new run starts with.
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            homing: (profile.upgrade_level(PermanentUpgrade::Homing) > 0).then(Homing::default),
+            bounces: profile.upgrade_level(PermanentUpgrade::Bounces),
+            chains: profile.upgrade_level(PermanentUpgrade::Chains),
+            split: (profile.upgrade_level(PermanentUpgrade::Split) > 0).then(Split::default),
+        }
+    }
+}
+
+impl Default for Homing {
+    fn default() -> Self {
+        Self {
+            turn_rate: HOMING_TURN_RATE,
+            radius: HOMING_RADIUS,
+        }
+    }
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Self {
+            fragments: SPLIT_FRAGMENTS,
+            damage_fraction: SPLIT_DAMAGE_FRACTION,
+        }
+    }
+}
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                steer_homing_bullets.before(update_bullets),
+                bounce_bullets.after(update_bullets),
+                (split_bullets, chain_bullets)
+                    .chain()
+                    .after(handle_bullet_collision)
+                    .before(despawn_bullets),
+            )
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
+        );
+    }
+}
+
+fn face(transform: &mut Transform, direction: Vec3) {
+    transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+}
+
+/// Finds the closest enemy to `position` within `radius`, skipping `exclude`.
+fn find_target(
+    tree: &EnemyKdTree,
+    position: Vec2,
+    radius: f32,
+    exclude: Option<Entity>,
+) -> Option<Entity> {
+    let nearest = tree
+        .tree
+        .nearest(&[position.x, position.y], 2, &squared_euclidean)
+        .ok()?;
+
+    nearest
+        .into_iter()
+        .find(|(distance, &entity)| Some(entity) != exclude && distance.sqrt() <= radius)
+        .map(|(_, &entity)| entity)
+}
+
+#[allow(clippy::type_complexity)]
+fn steer_homing_bullets(
+    time_scale: Res<TimeScale>,
+    tree: Res<EnemyKdTree>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Bullet>)>,
+    mut bullet_query: Query<
+        (&mut Transform, &mut Direction, &ProjectileModifiers),
+        (With<Bullet>, Without<Enemy>),
+    >,
+) {
+    for (mut transform, mut direction, modifiers) in bullet_query.iter_mut() {
+        let Some(homing) = modifiers.homing else {
+            continue;
+        };
+
+        let position = transform.translation.truncate();
+        let Some(target) = find_target(&tree, position, homing.radius, None) else {
+            continue;
+        };
+        let Ok(target_transform) = enemy_query.get(target) else {
+            continue;
+        };
+
+        let desired = (target_transform.translation.truncate() - position).normalize_or_zero();
+        let current = direction.value.truncate().normalize_or_zero();
+        let turn = (homing.turn_rate * time_scale.factor()).min(1.0);
+        let steered = current.lerp(desired, turn).normalize_or_zero();
+        if steered == Vec2::ZERO {
+            continue;
+        }
+
+        direction.value = steered.extend(direction.value.z);
+        face(&mut transform, direction.value);
+    }
+}
+
+fn bounce_bullets(
+    mut bullet_query: Query<
+        (&mut Transform, &mut Direction, &mut ProjectileModifiers),
+        With<Bullet>,
+    >,
+) {
+    let extents = (BACKGROUND_SIZE - BACKGROUND_OFFSET) / 2.0;
+
+    for (mut transform, mut direction, mut modifiers) in bullet_query.iter_mut() {
+        if modifiers.bounces == 0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let mut bounced = false;
+        if position.x.abs() > extents.x {
+            direction.value.x = -direction.value.x;
+            bounced = true;
+        }
+        if position.y.abs() > extents.y {
+            direction.value.y = -direction.value.y;
+            bounced = true;
+        }
+
+        if bounced {
+            modifiers.bounces -= 1;
+            let clamped = position.clamp(-extents, extents);
+            transform.translation.x = clamped.x;
+            transform.translation.y = clamped.y;
+            face(&mut transform, direction.value);
+        }
+    }
+}
+
+fn split_bullets(
+    mut commands: Commands,
+    handle: Res<GlobalTextureAtlas>,
+    mut hit_events: EventReader<BulletHit>,
+    bullet_query: Query<(&Direction, &ProjectileModifiers, WeaponStats), With<Bullet>>,
+) {
+    for hit in hit_events.read() {
+        let Ok((direction, modifiers, stats)) = bullet_query.get(hit.bullet) else {
+            continue;
+        };
+        let Some(split) = modifiers.split else {
+            continue;
+        };
+        if split.fragments == 0 {
+            continue;
+        }
+
+        let base_angle = direction.value.y.atan2(direction.value.x);
+        let step = 2.0 * PI / split.fragments as f32;
+        for index in 0..split.fragments {
+            let angle = base_angle + step * (index as f32 + 0.5);
+            let fragment_direction = Vec2::from_angle(angle);
+            // Start the fragment clear of the enemy that was just hit.
+            let origin = hit.position + fragment_direction * BULLET_SPEED * 1.5;
+
+            let mut fragment = stats.to_bundle();
+            fragment.damage.value *= split.damage_fraction;
+            fragment.range.value = SPLIT_RANGE;
+            fragment.pierces.value = 1;
+            fragment.modifiers = ProjectileModifiers {
+                homing: modifiers.homing,
+                ..default()
+            };
+
+            fire_bullet(
+                &mut commands,
+                &handle,
+                origin,
+                fragment_direction.extend(0.0),
+                fragment,
+            );
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn chain_bullets(
+    tree: Res<EnemyKdTree>,
+    mut hit_events: EventReader<BulletHit>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Bullet>)>,
+    mut bullet_query: Query<
+        (
+            &mut Transform,
+            &mut Direction,
+            &mut Position,
+            &mut Pierces,
+            &mut ProjectileModifiers,
+        ),
+        (With<Bullet>, Without<Enemy>),
+    >,
+) {
+    for hit in hit_events.read() {
+        let Ok((mut transform, mut direction, mut origin, mut pierces, mut modifiers)) =
+            bullet_query.get_mut(hit.bullet)
+        else {
+            continue;
+        };
+        if modifiers.chains == 0 {
+            continue;
+        }
+
+        let Some(next) = find_target(&tree, hit.position, CHAIN_RANGE, Some(hit.enemy)) else {
+            continue;
+        };
+        let Ok(next_transform) = enemy_query.get(next) else {
+            continue;
+        };
+
+        let chain_direction =
+            (next_transform.translation.truncate() - hit.position).normalize_or_zero();
+        if chain_direction == Vec2::ZERO {
+            continue;
+        }
+
+        // A chain jump refunds the pierce spent on the hit and restarts the range.
+        modifiers.chains -= 1;
+        pierces.value += 1;
+        origin.value = hit.position;
+        direction.value = chain_direction.extend(direction.value.z);
+        face(&mut transform, direction.value);
+    }
+}
@@ -1,14 +1,41 @@
+use std::collections::HashMap;
+
+use crate::atlas::{AtlasManifest, AtlasManifestLoader, SpriteAnimation};
 use crate::configs::*;
+use crate::menu::QuitRequested;
 use crate::state::GameState;
+use bevy::asset::{LoadState, RecursiveDependencyLoadState};
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
 pub struct ResourcesPlugin;
 
-#[derive(Resource)]
+/// The sprite sheet and the names of its frames, filled in from the atlas manifest.
+#[derive(Resource, Default)]
 pub struct GlobalTextureAtlas {
-    pub layout: Option<Handle<TextureAtlasLayout>>,
-    pub image: Option<Handle<Image>>,
+    pub manifest: Handle<AtlasManifest>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub image: Handle<Image>,
+    pub sprites: HashMap<String, usize>,
+    pub animations: HashMap<String, SpriteAnimation>,
+}
+
+#[derive(Resource, Default)]
+pub struct GlobalFont {
+    pub handle: Handle<Font>,
+}
+
+/// Handles the `Loading` state waits on before the game can start.
+#[derive(Resource, Default)]
+pub struct LoadingAssets {
+    pub handles: Vec<UntypedHandle>,
+    pub loaded: usize,
+    /// Path and reason of the first asset that failed to load.
+    pub failure: Option<(String, String)>,
 }
 
 #[derive(Resource)]
@@ -16,12 +43,87 @@ pub struct CursorPosition {
     pub value: Option<Vec2>,
 }
 
+/// Seeded RNG used for every gameplay roll so a run can be reproduced.
+#[derive(Resource)]
+pub struct GameRng {
+    pub rng: ChaCha8Rng,
+}
+
+/// Position in the RNG stream, so a resumed run continues the same sequence.
+/// The word position is split in two because RON has no 128-bit integers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    pub word_pos_high: u64,
+    pub word_pos_low: u64,
+}
+
+impl GameRng {
+    pub fn state(&self) -> RngState {
+        let word_pos = self.rng.get_word_pos();
+        RngState {
+            seed: self.rng.get_seed(),
+            stream: self.rng.get_stream(),
+            word_pos_high: (word_pos >> 64) as u64,
+            word_pos_low: word_pos as u64,
+        }
+    }
+
+    pub fn restore(&mut self, state: &RngState) {
+        let mut rng = ChaCha8Rng::from_seed(state.seed);
+        rng.set_stream(state.stream);
+        rng.set_word_pos(((state.word_pos_high as u128) << 64) | state.word_pos_low as u128);
+        self.rng = rng;
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(rand::random()),
+        }
+    }
+}
+
+impl GlobalTextureAtlas {
+    /// Atlas index of a named sprite. Names in `REQUIRED_SPRITES` are checked at load time.
+    pub fn sprite(&self, name: &str) -> usize {
+        self.sprites.get(name).copied().unwrap_or_else(|| {
+            warn!("Unknown sprite \"{}\"", name);
+            0
+        })
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&SpriteAnimation> {
+        self.animations.get(name)
+    }
+
+    /// Atlas pointing at a named sprite, ready to put on an entity.
+    pub fn texture_atlas(&self, name: &str) -> TextureAtlas {
+        TextureAtlas {
+            layout: self.layout.clone(),
+            index: self.sprite(name),
+        }
+    }
+}
+
 impl Plugin for ResourcesPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(GlobalTextureAtlas::default())
+        app.init_asset::<AtlasManifest>()
+            .init_asset_loader::<AtlasManifestLoader>()
+            .insert_resource(GlobalTextureAtlas::default())
+            .init_resource::<GlobalFont>()
             .insert_resource(CursorPosition { value: None })
+            .init_resource::<GameRng>()
+            .init_resource::<LoadingAssets>()
             .add_systems(OnEnter(GameState::Loading), load_assets)
-            .add_systems(Update, close_on_esc)
+            .add_systems(
+                Update,
+                (check_assets_loaded, loading_ui)
+                    .chain()
+                    .run_if(in_state(GameState::Loading)),
+            )
             .add_systems(
                 Update,
                 update_cursor_position.run_if(in_state(GameState::InGame)),
@@ -31,22 +133,117 @@ impl Plugin for ResourcesPlugin {
 
 fn load_assets(
     mut handle: ResMut<GlobalTextureAtlas>,
+    mut font: ResMut<GlobalFont>,
+    mut loading: ResMut<LoadingAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    handle.manifest = asset_server.load(ATLAS_MANIFEST_PATH);
+    loading.handles.push(handle.manifest.clone().untyped());
+
+    font.handle = asset_server.load(FONT_PATH);
+    loading.handles.push(font.handle.clone().untyped());
+}
+
+fn check_assets_loaded(
+    mut loading: ResMut<LoadingAssets>,
     asset_server: Res<AssetServer>,
+    mut handle: ResMut<GlobalTextureAtlas>,
+    manifests: Res<Assets<AtlasManifest>>,
+    images: Res<Assets<Image>>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    handle.image = Some(asset_server.load(SPRITE_SHEET_PATH));
-
-    let layout = TextureAtlasLayout::from_grid(
-        UVec2::new(TILE_WIDTH, TILE_HEIGHT),
-        SPRITE_SHEET_WIDTH,
-        SPRITE_SHEET_HEIGHT,
-        None,
-        None,
-    );
-    handle.layout = Some(texture_atlas_layouts.add(layout));
-
-    next_state.set(GameState::GameInit);
+    if loading.failure.is_some() {
+        return;
+    }
+
+    let mut loaded = 0;
+    let mut failure = None;
+    for handle in loading.handles.iter() {
+        let path = handle
+            .path()
+            .map(|path| path.to_string())
+            .unwrap_or_default();
+        match asset_server.get_load_state(handle.id()) {
+            Some(LoadState::Failed(error)) => {
+                failure = Some((path, error.to_string()));
+                break;
+            }
+            _ if asset_server.get_recursive_dependency_load_state(handle.id())
+                == Some(RecursiveDependencyLoadState::Failed) =>
+            {
+                failure = Some((path, "a dependency failed to load".to_string()));
+                break;
+            }
+            _ if asset_server.is_loaded_with_dependencies(handle.id()) => loaded += 1,
+            _ => {}
+        }
+    }
+
+    loading.loaded = loaded;
+    if failure.is_none() && loaded == loading.handles.len() {
+        match build_texture_atlas(&mut handle, &manifests, &images, &mut texture_atlas_layouts) {
+            Ok(()) => next_state.set(GameState::MainMenu),
+            Err(error) => failure = Some((ATLAS_MANIFEST_PATH.to_string(), error)),
+        }
+    }
+
+    if let Some((path, error)) = failure {
+        error!("Failed to load {}: {}", path, error);
+        loading.failure = Some((path, error));
+    }
+}
+
+/// Validates the loaded manifest against its sprite sheet and builds the atlas from it.
+fn build_texture_atlas(
+    handle: &mut GlobalTextureAtlas,
+    manifests: &Assets<AtlasManifest>,
+    images: &Assets<Image>,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+) -> Result<(), String> {
+    let manifest = manifests
+        .get(&handle.manifest)
+        .ok_or("manifest is not loaded")?;
+    let image = images
+        .get(&manifest.texture)
+        .ok_or("sprite sheet is not loaded")?;
+    manifest.validate(image.size())?;
+
+    handle.image = manifest.texture.clone();
+    handle.layout = texture_atlas_layouts.add(manifest.layout());
+    handle.sprites = manifest.sprites.clone();
+    handle.animations = manifest.animations.clone();
+    Ok(())
+}
+
+fn loading_ui(
+    mut contexts: EguiContexts,
+    loading: Res<LoadingAssets>,
+    mut quit: ResMut<QuitRequested>,
+) {
+    egui::Window::new("Loading")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| match &loading.failure {
+            Some((path, error)) => {
+                ui.colored_label(egui::Color32::RED, format!("Could not load {}", path));
+                ui.label(error);
+                if ui.button("Quit").clicked() {
+                    quit.0 = true;
+                }
+            }
+            None => {
+                let total = loading.handles.len().max(1);
+                ui.add(
+                    egui::ProgressBar::new(loading.loaded as f32 / total as f32).text(format!(
+                        "{}/{}",
+                        loading.loaded,
+                        loading.handles.len()
+                    )),
+                );
+            }
+        });
 }
 
 fn update_cursor_position(
@@ -70,28 +267,3 @@ fn update_cursor_position(
         .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
         .map(|ray| ray.origin.truncate());
 }
-
-fn close_on_esc(
-    mut commands: Commands,
-    focused_windows: Query<(Entity, &Window)>,
-    input: Res<ButtonInput<KeyCode>>,
-) {
-    for (window, focus) in focused_windows.iter() {
-        if !focus.focused {
-            continue;
-        }
-
-        if input.just_pressed(KeyCode::Escape) {
-            commands.entity(window).despawn();
-        }
-    }
-}
-
-impl Default for GlobalTextureAtlas {
-    fn default() -> Self {
-        Self {
-            layout: None,
-            image: None,
-        }
-    }
-}
@@ -0,0 +1,256 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use bevy::scene::SceneFilter;
+
+use crate::ability::{Abilities, Summon};
+use crate::animation::AnimationPlayer;
+use crate::components::{
+    AttackSpeed, AttackSpeedPercent, CritChance, CritMultiplier, Damage, DamageType,
+    DamageVariance, Direction, GunBag, Health, HurtCooldown, Knockback, Mass, MaxHealth,
+    NearestEnemy, Pierces, Position, Range, Speed,
+};
+use crate::configs::*;
+use crate::dash::Dash;
+use crate::enemy::{Enemy, EnemyType, HitStun, LastDamageSource};
+use crate::gun::{Bullet, Gun};
+use crate::overlay::HealthBar;
+use crate::pickup::{Experience, XpOrb};
+use crate::player::{despawn_run, Player, RunEntityFilter};
+use crate::prestige::GlobalMultipliers;
+use crate::projectile::ProjectileModifiers;
+use crate::resources::GlobalTextureAtlas;
+use crate::state::GameState;
+use crate::status::{OnHitEffects, StatusEffects};
+
+pub struct GameScenePlugin;
+
+/// Writes every run entity to `assets/scenes/<name>.scn.ron`.
+#[derive(Event)]
+pub struct SaveScene(pub String);
+
+/// Replaces the current run with the entities in `assets/scenes/<name>.scn.ron`.
+#[derive(Event)]
+pub struct LoadScene(pub String);
+
+impl Plugin for GameScenePlugin {
+    fn build(&self, app: &mut App) {
+        register_gameplay_types(app);
+        app.add_event::<SaveScene>()
+            .add_event::<LoadScene>()
+            .add_systems(
+                Update,
+                (save_scenes, load_scenes, dress_scene_entities)
+                    .chain()
+                    .run_if(in_state(GameState::InGame).or_else(in_state(GameState::Paused))),
+            );
+    }
+}
+
+/// Every component a run is made of. Only these and `Transform` end up in saved scenes.
+pub fn register_gameplay_types(app: &mut App) {
+    app.register_type::<Player>()
+        .register_type::<Enemy>()
+        .register_type::<EnemyType>()
+        .register_type::<HitStun>()
+        .register_type::<LastDamageSource>()
+        .register_type::<Gun>()
+        .register_type::<Bullet>()
+        .register_type::<XpOrb>()
+        .register_type::<Experience>()
+        .register_type::<Dash>()
+        .register_type::<Abilities>()
+        .register_type::<Summon>()
+        .register_type::<GlobalMultipliers>()
+        .register_type::<ProjectileModifiers>()
+        .register_type::<StatusEffects>()
+        .register_type::<OnHitEffects>()
+        .register_type::<Speed>()
+        .register_type::<Health>()
+        .register_type::<MaxHealth>()
+        .register_type::<HurtCooldown>()
+        .register_type::<GunBag>()
+        .register_type::<NearestEnemy>()
+        .register_type::<AttackSpeed>()
+        .register_type::<AttackSpeedPercent>()
+        .register_type::<Damage>()
+        .register_type::<Range>()
+        .register_type::<Direction>()
+        .register_type::<Position>()
+        .register_type::<Pierces>()
+        .register_type::<CritChance>()
+        .register_type::<CritMultiplier>()
+        .register_type::<DamageVariance>()
+        .register_type::<Knockback>()
+        .register_type::<Mass>()
+        .register_type::<DamageType>();
+}
+
+/// Allows `Transform` and the components this crate registers, leaving out sprites,
+/// hierarchy and anything else that is rebuilt when the scene is spawned.
+pub fn gameplay_filter(registry: &AppTypeRegistry) -> SceneFilter {
+    let crate_prefix = concat!(env!("CARGO_CRATE_NAME"), "::");
+    registry
+        .read()
+        .iter()
+        .filter(|registration| {
+            registration.data::<ReflectComponent>().is_some()
+                && registration
+                    .type_info()
+                    .type_path()
+                    .starts_with(crate_prefix)
+        })
+        .fold(
+            SceneFilter::deny_all().allow::<Transform>(),
+            |filter, registration| filter.allow_by_id(registration.type_id()),
+        )
+}
+
+/// Asset path of a scene. Names can't reach outside the scene directory.
+pub fn scene_path(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!("invalid scene name \"{}\"", name));
+    }
+    Ok(format!("{}/{}.{}", SCENE_DIRECTORY, name, SCENE_EXTENSION))
+}
+
+fn scene_file(name: &str) -> Result<PathBuf, String> {
+    let path = scene_path(name)?;
+    Ok(FileAssetReader::get_base_path().join("assets").join(path))
+}
+
+fn save_scenes(world: &mut World) {
+    let names: Vec<String> = world
+        .resource_mut::<Events<SaveScene>>()
+        .drain()
+        .map(|SaveScene(name)| name)
+        .collect();
+    if names.is_empty() {
+        return;
+    }
+
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, RunEntityFilter>()
+        .iter(world)
+        .collect();
+    let filter = gameplay_filter(world.resource::<AppTypeRegistry>());
+    let scene = DynamicSceneBuilder::from_world(world)
+        .with_filter(filter)
+        .extract_entities(entities.into_iter())
+        .build();
+
+    let serialized = scene.serialize(&world.resource::<AppTypeRegistry>().read());
+    let contents = match serialized {
+        Ok(contents) => contents,
+        Err(error) => {
+            warn!("Could not serialize scene: {}", error);
+            return;
+        }
+    };
+
+    for name in names {
+        let path = match scene_file(&name) {
+            Ok(path) => path,
+            Err(error) => {
+                warn!("Could not save scene: {}", error);
+                continue;
+            }
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, &contents));
+        match written {
+            Ok(()) => info!("Saved scene {}", path.display()),
+            Err(error) => warn!("Could not save scene {}: {}", path.display(), error),
+        }
+    }
+}
+
+fn load_scenes(
+    mut commands: Commands,
+    mut load_events: EventReader<LoadScene>,
+    asset_server: Res<AssetServer>,
+    mut scene_spawner: ResMut<SceneSpawner>,
+    run_query: Query<Entity, RunEntityFilter>,
+) {
+    let Some(LoadScene(name)) = load_events.read().last() else {
+        return;
+    };
+
+    let path = match scene_path(name) {
+        Ok(path) => path,
+        Err(error) => {
+            warn!("Could not load scene: {}", error);
+            return;
+        }
+    };
+
+    despawn_run(&mut commands, &run_query);
+    scene_spawner.spawn_dynamic(asset_server.load::<DynamicScene>(path));
+}
+
+/// Scenes only carry gameplay components, so entities spawned from one get their
+/// sprite, animation and health bar here.
+#[allow(clippy::type_complexity)]
+fn dress_scene_entities(
+    mut commands: Commands,
+    handle: Res<GlobalTextureAtlas>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            Has<Player>,
+            Option<&EnemyType>,
+            Has<Gun>,
+            Has<Summon>,
+            Has<XpOrb>,
+        ),
+        (RunEntityFilter, Without<Sprite>),
+    >,
+) {
+    for (entity, transform, is_player, enemy_type, is_gun, is_summon, is_orb) in query.iter() {
+        let mut entity_commands = commands.entity(entity);
+        if is_orb {
+            entity_commands.insert(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::srgb_u8(XP_ORB_COLOR.0, XP_ORB_COLOR.1, XP_ORB_COLOR.2),
+                    custom_size: Some(Vec2::splat(XP_ORB_SIZE)),
+                    ..default()
+                },
+                transform: *transform,
+                ..default()
+            });
+            continue;
+        }
+
+        let sprite = if is_player {
+            SPRITE_PLAYER
+        } else if let Some(enemy_type) = enemy_type {
+            enemy_type.sprite_name()
+        } else if is_gun || is_summon {
+            SPRITE_GUN
+        } else {
+            SPRITE_BULLET
+        };
+        entity_commands.insert((
+            SpriteBundle {
+                texture: handle.image.clone(),
+                transform: *transform,
+                ..default()
+            },
+            handle.texture_atlas(sprite),
+        ));
+
+        if is_player || enemy_type.is_some() || is_gun || is_summon {
+            entity_commands.insert(AnimationPlayer::new(sprite));
+        }
+        if is_player || enemy_type.is_some() {
+            entity_commands.insert(HealthBar {
+                always_visible: is_player,
+            });
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::{Action, ActionInput};
+use crate::configs::*;
+use crate::events::EnemyKilled;
+use crate::player::Player;
+use crate::prestige::{GlobalMultipliers, PrestigeBonus, PrestigeOpen};
+use crate::profile::Profile;
+use crate::state::GameState;
+
+pub struct ShopPlugin;
+
+/// Upgrades bought with gold that carry over to every future run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PermanentUpgrade {
+    MaxHealth,
+    Speed,
+    Damage,
+    AttackSpeed,
+    Range,
+    StartingGuns,
+    GunCapacity,
+    DashCooldown,
+    DashCharges,
+    Homing,
+    Bounces,
+    Chains,
+    Split,
+}
+
+#[derive(Resource, Default)]
+pub struct ShopOpen(pub bool);
+
+impl PermanentUpgrade {
+    pub const ALL: [PermanentUpgrade; 13] = [
+        PermanentUpgrade::MaxHealth,
+        PermanentUpgrade::Speed,
+        PermanentUpgrade::Damage,
+        PermanentUpgrade::AttackSpeed,
+        PermanentUpgrade::Range,
+        PermanentUpgrade::StartingGuns,
+        PermanentUpgrade::GunCapacity,
+        PermanentUpgrade::DashCooldown,
+        PermanentUpgrade::DashCharges,
+        PermanentUpgrade::Homing,
+        PermanentUpgrade::Bounces,
+        PermanentUpgrade::Chains,
+        PermanentUpgrade::Split,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PermanentUpgrade::MaxHealth => "Max health",
+            PermanentUpgrade::Speed => "Speed",
+            PermanentUpgrade::Damage => "Damage",
+            PermanentUpgrade::AttackSpeed => "Attack speed",
+            PermanentUpgrade::Range => "Range",
+            PermanentUpgrade::StartingGuns => "Starting guns",
+            PermanentUpgrade::GunCapacity => "Gun bag capacity",
+            PermanentUpgrade::DashCooldown => "Dash cooldown",
+            PermanentUpgrade::DashCharges => "Dash charges",
+            PermanentUpgrade::Homing => "Homing bullets",
+            PermanentUpgrade::Bounces => "Bullet bounces",
+            PermanentUpgrade::Chains => "Bullet chains",
+            PermanentUpgrade::Split => "Splitting bullets",
+        }
+    }
+
+    /// Stat bonus granted per level.
+    pub fn step(&self) -> f32 {
+        match self {
+            PermanentUpgrade::MaxHealth => 10.0,
+            PermanentUpgrade::Speed => 0.25,
+            PermanentUpgrade::Damage => 2.0,
+            PermanentUpgrade::AttackSpeed => 5.0,
+            PermanentUpgrade::Range => 10.0,
+            PermanentUpgrade::StartingGuns | PermanentUpgrade::GunCapacity => 1.0,
+            // Seconds taken off the cooldown
+            PermanentUpgrade::DashCooldown => 0.15,
+            PermanentUpgrade::DashCharges => 1.0,
+            PermanentUpgrade::Homing
+            | PermanentUpgrade::Bounces
+            | PermanentUpgrade::Chains
+            | PermanentUpgrade::Split => 1.0,
+        }
+    }
+
+    pub fn max_level(&self) -> u32 {
+        match self {
+            PermanentUpgrade::StartingGuns => GUN_OFFSET.len() as u32 - 1,
+            PermanentUpgrade::GunCapacity => GUN_OFFSET.len() as u32 - GUN_BAG_BASE_CAPACITY,
+            PermanentUpgrade::DashCharges => DASH_MAX_CHARGES - DASH_BASE_CHARGES,
+            // Homing and split are either on or off
+            PermanentUpgrade::Homing | PermanentUpgrade::Split => 1,
+            PermanentUpgrade::Bounces | PermanentUpgrade::Chains => SHOP_MAX_BOUNCES_AND_CHAINS,
+            _ => 10,
+        }
+    }
+
+    pub fn base_cost(&self) -> u64 {
+        match self {
+            PermanentUpgrade::StartingGuns
+            | PermanentUpgrade::GunCapacity
+            | PermanentUpgrade::DashCharges => 100,
+            PermanentUpgrade::Homing
+            | PermanentUpgrade::Bounces
+            | PermanentUpgrade::Chains
+            | PermanentUpgrade::Split => 150,
+            _ => 25,
+        }
+    }
+
+    /// Gold needed to buy the next level, or `None` once maxed out.
+    pub fn cost(&self, level: u32) -> Option<u64> {
+        if level >= self.max_level() {
+            return None;
+        }
+        Some((self.base_cost() as f32 * SHOP_COST_GROWTH.powi(level as i32)) as u64)
+    }
+}
+
+impl Profile {
+    pub fn upgrade_level(&self, upgrade: PermanentUpgrade) -> u32 {
+        self.upgrades.get(&upgrade).copied().unwrap_or_default()
+    }
+
+    /// Total bonus from an upgrade at its current level.
+    pub fn upgrade_bonus(&self, upgrade: PermanentUpgrade) -> f32 {
+        self.upgrade_level(upgrade) as f32 * upgrade.step()
+    }
+
+    /// Spends gold on the next level of an upgrade. Returns false if it can't be afforded.
+    pub fn buy_upgrade(&mut self, upgrade: PermanentUpgrade) -> bool {
+        let level = self.upgrade_level(upgrade);
+        let Some(cost) = upgrade.cost(level) else {
+            return false;
+        };
+        if self.gold < cost {
+            return false;
+        }
+
+        self.gold -= cost;
+        self.upgrades.insert(upgrade, level + 1);
+        true
+    }
+}
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShopOpen>()
+            .add_systems(
+                Update,
+                (earn_gold, toggle_shop).run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Update, shop_ui);
+    }
+}
+
+/// Pays out whole gold, carrying the fraction left by the gold multiplier to later kills.
+fn earn_gold(
+    mut remainder: Local<f64>,
+    mut profile: ResMut<Profile>,
+    mut killed_events: EventReader<EnemyKilled>,
+    player_query: Query<&GlobalMultipliers, With<Player>>,
+) {
+    let kills = killed_events.read().count() as u64;
+    if kills > 0 {
+        let multiplier = player_query
+            .get_single()
+            .map(|multipliers| multipliers.gold_gain)
+            .unwrap_or_else(|_| profile.prestige_multiplier(PrestigeBonus::GoldGain));
+        let gold = kills as f64 * GOLD_PER_KILL as f64 * multiplier as f64 + *remainder;
+        *remainder = gold.fract();
+        profile.add_gold(gold as u64);
+    }
+}
+
+fn toggle_shop(input: ActionInput, mut shop_open: ResMut<ShopOpen>) {
+    if input.just_pressed(Action::ToggleShop) {
+        shop_open.0 = !shop_open.0;
+    }
+}
+
+fn shop_ui(
+    mut contexts: EguiContexts,
+    mut shop_open: ResMut<ShopOpen>,
+    mut prestige_open: ResMut<PrestigeOpen>,
+    mut profile: ResMut<Profile>,
+) {
+    if !shop_open.0 {
+        return;
+    }
+
+    egui::Window::new("Shop")
+        .open(&mut shop_open.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Gold: {}", profile.gold));
+            ui.label("Upgrades apply from the next run.");
+            ui.separator();
+
+            egui::Grid::new("shop_grid").striped(true).show(ui, |ui| {
+                for upgrade in PermanentUpgrade::ALL {
+                    let level = profile.upgrade_level(upgrade);
+                    ui.label(upgrade.name());
+                    ui.label(format!("{}/{}", level, upgrade.max_level()));
+
+                    match upgrade.cost(level) {
+                        Some(cost) => {
+                            let affordable = profile.gold >= cost;
+                            let button = ui.add_enabled(
+                                affordable,
+                                egui::Button::new(format!("Buy ({} gold)", cost)),
+                            );
+                            if button.clicked() {
+                                profile.buy_upgrade(upgrade);
+                            }
+                        }
+                        None => {
+                            ui.label("Maxed");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            if ui.button("Prestige").clicked() {
+                prestige_open.0 = !prestige_open.0;
+            }
+        });
+}
@@ -0,0 +1,395 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::ability::{spawn_summon, Abilities, AbilityBook, AbilityBookHandle, Summon};
+use crate::components::{AttackSpeed, Direction, Health, Position};
+use crate::configs::*;
+use crate::enemy::{spawn_enemy, Enemy, EnemySpawnTimer, EnemyType, HitStun};
+use crate::gun::{fire_bullet, spawn_gun_entity, Bullet, Gun, WeaponStats, WeaponStatsBundle};
+use crate::pickup::{spawn_xp_orb, XpOrb};
+use crate::player::{
+    despawn_run, spawn_player, Player, PlayerStats, PlayerStatsBundle, RunEntityFilter,
+};
+use crate::profile::{data_path, write_ron_atomic, Profile};
+use crate::resources::{GameRng, GlobalTextureAtlas, RngState};
+use crate::state::GameState;
+use crate::stats::RunStats;
+use crate::status::StatusEffects;
+
+pub struct SnapshotPlugin;
+
+/// Bump when the snapshot layout changes. Snapshots of any other version are discarded.
+pub const RUN_SNAPSHOT_VERSION: u32 = 4;
+
+/// Everything needed to put a run back exactly as it was when the game was closed.
+#[derive(Resource, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    pub version: u32,
+    pub player: PlayerSnapshot,
+    pub guns: Vec<GunSnapshot>,
+    pub enemies: Vec<EnemySnapshot>,
+    pub bullets: Vec<BulletSnapshot>,
+    pub xp_orbs: Vec<XpOrbSnapshot>,
+    pub summons: Vec<SummonSnapshot>,
+    /// Seconds into the current wave.
+    pub spawn_timer_elapsed: f32,
+    pub elapsed: f32,
+    pub kills: u64,
+    pub rng: RngState,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub position: Vec2,
+    pub stats: PlayerStatsBundle,
+    pub abilities: Vec<AbilitySlotSnapshot>,
+}
+
+/// Cooldown state of one ability bar slot. The ability itself comes from the ability book.
+#[derive(Serialize, Deserialize)]
+pub struct AbilitySlotSnapshot {
+    pub name: String,
+    pub remaining: f32,
+    pub auto_cast: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GunSnapshot {
+    pub attack_speed: AttackSpeed,
+    pub stats: WeaponStatsBundle,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EnemySnapshot {
+    pub position: Vec2,
+    pub enemy_type: EnemyType,
+    pub health: Health,
+    pub hit_stun: HitStun,
+    pub status_effects: StatusEffects,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BulletSnapshot {
+    pub transform: Transform,
+    pub origin: Position,
+    pub direction: Direction,
+    pub stats: WeaponStatsBundle,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct XpOrbSnapshot {
+    pub position: Vec2,
+    pub orb: XpOrb,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SummonSnapshot {
+    pub position: Vec2,
+    pub summon: Summon,
+    pub attack_speed: AttackSpeed,
+    pub stats: WeaponStatsBundle,
+}
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_run_snapshot)
+            .add_systems(
+                Update,
+                resume_run_ui
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<RunSnapshot>),
+            )
+            .add_systems(
+                Last,
+                save_run_on_exit.run_if(not(resource_exists::<RunSnapshot>)),
+            );
+    }
+}
+
+impl RunSnapshot {
+    pub fn path() -> Option<PathBuf> {
+        data_path(RUN_SNAPSHOT_FILE_NAME)
+    }
+
+    /// Deletes the snapshot from disk so it is only ever resumed once.
+    pub fn discard() {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Err(error) = fs::remove_file(path) {
+            if error.kind() != io::ErrorKind::NotFound {
+                warn!("Could not delete run snapshot: {}", error);
+            }
+        }
+    }
+}
+
+fn load_run_snapshot(mut commands: Commands) {
+    let Some(path) = RunSnapshot::path() else {
+        return;
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return,
+        Err(error) => {
+            warn!("Could not read run snapshot {}: {}", path.display(), error);
+            return;
+        }
+    };
+
+    match ron::from_str::<RunSnapshot>(&contents) {
+        Ok(snapshot) if snapshot.version == RUN_SNAPSHOT_VERSION => {
+            commands.insert_resource(snapshot)
+        }
+        Ok(snapshot) => {
+            warn!(
+                "Discarding run snapshot with unsupported version {}",
+                snapshot.version
+            );
+            RunSnapshot::discard();
+        }
+        Err(error) => {
+            warn!("Discarding unreadable run snapshot: {}", error);
+            RunSnapshot::discard();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_run_on_exit(
+    exit_events: EventReader<AppExit>,
+    player_query: Query<(&Transform, PlayerStats, Option<&Abilities>), With<Player>>,
+    gun_query: Query<(&AttackSpeed, WeaponStats), With<Gun>>,
+    enemy_query: Query<(&Transform, &EnemyType, &Health, &HitStun, &StatusEffects), With<Enemy>>,
+    bullet_query: Query<(&Transform, &Position, &Direction, WeaponStats), With<Bullet>>,
+    orb_query: Query<(&Transform, &XpOrb)>,
+    summon_query: Query<(&Transform, &Summon, &AttackSpeed, WeaponStats)>,
+    spawn_timer: Res<EnemySpawnTimer>,
+    run_stats: Res<RunStats>,
+    rng: Res<GameRng>,
+) {
+    if exit_events.is_empty() {
+        return;
+    }
+    let Ok((player_transform, player_stats, abilities)) = player_query.get_single() else {
+        return;
+    };
+    let Some(path) = RunSnapshot::path() else {
+        return;
+    };
+
+    let snapshot = RunSnapshot {
+        version: RUN_SNAPSHOT_VERSION,
+        player: PlayerSnapshot {
+            position: player_transform.translation.truncate(),
+            stats: player_stats.to_bundle(),
+            abilities: abilities
+                .map(|abilities| {
+                    abilities
+                        .slots
+                        .iter()
+                        .map(|slot| AbilitySlotSnapshot {
+                            name: slot.ability.name.clone(),
+                            remaining: slot.remaining,
+                            auto_cast: slot.auto_cast,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        },
+        guns: gun_query
+            .iter()
+            .map(|(attack_speed, stats)| GunSnapshot {
+                attack_speed: attack_speed.clone(),
+                stats: stats.to_bundle(),
+            })
+            .collect(),
+        enemies: enemy_query
+            .iter()
+            .map(
+                |(transform, enemy_type, health, hit_stun, status_effects)| EnemySnapshot {
+                    position: transform.translation.truncate(),
+                    enemy_type: *enemy_type,
+                    health: health.clone(),
+                    hit_stun: hit_stun.clone(),
+                    status_effects: status_effects.clone(),
+                },
+            )
+            .collect(),
+        bullets: bullet_query
+            .iter()
+            .map(|(transform, origin, direction, stats)| BulletSnapshot {
+                transform: *transform,
+                origin: origin.clone(),
+                direction: direction.clone(),
+                stats: stats.to_bundle(),
+            })
+            .collect(),
+        xp_orbs: orb_query
+            .iter()
+            .map(|(transform, orb)| XpOrbSnapshot {
+                position: transform.translation.truncate(),
+                orb: orb.clone(),
+            })
+            .collect(),
+        summons: summon_query
+            .iter()
+            .map(|(transform, summon, attack_speed, stats)| SummonSnapshot {
+                position: transform.translation.truncate(),
+                summon: summon.clone(),
+                attack_speed: attack_speed.clone(),
+                stats: stats.to_bundle(),
+            })
+            .collect(),
+        spawn_timer_elapsed: spawn_timer.timer.elapsed_secs(),
+        elapsed: run_stats.elapsed,
+        kills: run_stats.kills,
+        rng: rng.state(),
+    };
+
+    if let Err(error) = write_ron_atomic(&path, &snapshot) {
+        warn!("Could not save run snapshot: {}", error);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn resume_run_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    snapshot: Res<RunSnapshot>,
+    handle: Res<GlobalTextureAtlas>,
+    run_query: Query<Entity, RunEntityFilter>,
+    mut spawn_timer: ResMut<EnemySpawnTimer>,
+    mut run_stats: ResMut<RunStats>,
+    mut rng: ResMut<GameRng>,
+    mut profile: ResMut<Profile>,
+    book: Res<AbilityBookHandle>,
+    books: Res<Assets<AbilityBook>>,
+) {
+    let minutes = snapshot.elapsed as u64 / 60;
+    let seconds = snapshot.elapsed as u64 % 60;
+    let mut resume = false;
+    let mut discard = false;
+
+    egui::Window::new("Resume run?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!(
+                "You left a run at {}:{:02} with {} kills.",
+                minutes, seconds, snapshot.kills
+            ));
+            ui.horizontal(|ui| {
+                resume = ui.button("Resume").clicked();
+                discard = ui.button("Discard").clicked();
+            });
+        });
+
+    if !resume && !discard {
+        return;
+    }
+
+    if resume {
+        despawn_run(&mut commands, &run_query);
+        restore_run(&mut commands, &handle, &snapshot, books.get(&book.0));
+
+        spawn_timer
+            .timer
+            .set_elapsed(Duration::from_secs_f32(snapshot.spawn_timer_elapsed));
+        run_stats.elapsed = snapshot.elapsed;
+        run_stats.kills = snapshot.kills;
+        rng.restore(&snapshot.rng);
+        // The resumed run was already counted when it started.
+        profile.statistics.runs = profile.statistics.runs.saturating_sub(1);
+    } else {
+        // A discarded run ends where it was left.
+        profile.record_time(snapshot.elapsed);
+    }
+
+    RunSnapshot::discard();
+    commands.remove_resource::<RunSnapshot>();
+}
+
+fn restore_run(
+    commands: &mut Commands,
+    handle: &GlobalTextureAtlas,
+    snapshot: &RunSnapshot,
+    book: Option<&AbilityBook>,
+) {
+    let player = spawn_player(
+        commands,
+        handle,
+        snapshot.player.position,
+        snapshot.player.stats.clone(),
+    );
+
+    // Slots are matched by name, so abilities added to the book since the save start ready
+    if let Some(book) = book {
+        let mut abilities = Abilities::from_book(book);
+        for slot in abilities.slots.iter_mut() {
+            let saved = snapshot
+                .player
+                .abilities
+                .iter()
+                .find(|saved| saved.name == slot.ability.name);
+            if let Some(saved) = saved {
+                slot.remaining = saved.remaining.min(slot.ability.cooldown);
+                slot.auto_cast = saved.auto_cast;
+            }
+        }
+        commands.entity(player).insert(abilities);
+    }
+
+    for gun in snapshot.guns.iter() {
+        spawn_gun_entity(
+            commands,
+            handle,
+            gun.attack_speed.clone(),
+            gun.stats.clone(),
+        );
+    }
+
+    for enemy in snapshot.enemies.iter() {
+        let entity = spawn_enemy(commands, handle, enemy.enemy_type, enemy.position);
+        commands.entity(entity).insert((
+            enemy.health.clone(),
+            enemy.hit_stun.clone(),
+            enemy.status_effects.clone(),
+        ));
+    }
+
+    for bullet in snapshot.bullets.iter() {
+        let entity = fire_bullet(
+            commands,
+            handle,
+            bullet.origin.value,
+            bullet.direction.value,
+            bullet.stats.clone(),
+        );
+        commands.entity(entity).insert(bullet.transform);
+    }
+
+    for xp_orb in snapshot.xp_orbs.iter() {
+        spawn_xp_orb(commands, xp_orb.position, xp_orb.orb.value);
+    }
+
+    for summon in snapshot.summons.iter() {
+        let entity = spawn_summon(
+            commands,
+            handle,
+            summon.position,
+            summon.summon.clone(),
+            summon.attack_speed.interval,
+            summon.stats.clone(),
+        );
+        commands.entity(entity).insert(summon.attack_speed.clone());
+    }
+}
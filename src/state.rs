@@ -4,6 +4,9 @@ use bevy::prelude::*;
 pub enum GameState {
     #[default]
     Loading,
+    MainMenu,
     GameInit,
     InGame,
+    Paused,
+    Settings,
 }
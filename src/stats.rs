@@ -0,0 +1,103 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{AbilityHit, BulletHit, EnemyKilled, PlayerDamaged, ShotFired};
+use crate::player::Player;
+use crate::profile::{save_profile_on_exit, Profile};
+use crate::state::GameState;
+
+pub struct StatsPlugin;
+
+/// Lifetime totals kept in the profile.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Statistics {
+    pub enemies_killed: u64,
+    pub shots_fired: u64,
+    pub damage_dealt: f64,
+    pub damage_taken: f64,
+    pub crits: u64,
+    pub runs: u64,
+    pub play_time_secs: f64,
+}
+
+/// Progress of the current run.
+#[derive(Resource, Debug, Default)]
+pub struct RunStats {
+    pub elapsed: f32,
+    pub kills: u64,
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(OnEnter(GameState::GameInit), start_run)
+            .add_systems(
+                Update,
+                (tick_run_clock, record_statistics).run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Last, record_run_on_exit.before(save_profile_on_exit));
+    }
+}
+
+/// Closes out the previous run, if any, and resets the run counters.
+fn start_run(mut run_stats: ResMut<RunStats>, mut profile: ResMut<Profile>) {
+    if run_stats.elapsed > 0.0 {
+        profile.record_time(run_stats.elapsed);
+    }
+    profile.statistics.runs += 1;
+    *run_stats = RunStats::default();
+}
+
+/// Records an abandoned run when the game closes, since `start_run` will never see it.
+/// A run still in progress goes into the run snapshot and is recorded once it ends.
+fn record_run_on_exit(
+    exit_events: EventReader<AppExit>,
+    run_stats: Res<RunStats>,
+    mut profile: ResMut<Profile>,
+    player_query: Query<(), With<Player>>,
+) {
+    if exit_events.is_empty() || run_stats.elapsed <= 0.0 || !player_query.is_empty() {
+        return;
+    }
+    profile.record_time(run_stats.elapsed);
+}
+
+fn tick_run_clock(time: Res<Time>, mut run_stats: ResMut<RunStats>, mut profile: ResMut<Profile>) {
+    run_stats.elapsed += time.delta_seconds();
+    profile.statistics.play_time_secs += time.delta_seconds_f64();
+}
+
+fn record_statistics(
+    mut run_stats: ResMut<RunStats>,
+    mut profile: ResMut<Profile>,
+    mut hit_events: EventReader<BulletHit>,
+    mut ability_events: EventReader<AbilityHit>,
+    mut killed_events: EventReader<EnemyKilled>,
+    mut damaged_events: EventReader<PlayerDamaged>,
+    mut shot_events: EventReader<ShotFired>,
+) {
+    let statistics = &mut profile.statistics;
+
+    for hit in hit_events.read() {
+        statistics.damage_dealt += hit.damage as f64;
+        if hit.crit {
+            statistics.crits += 1;
+        }
+    }
+
+    for hit in ability_events.read() {
+        statistics.damage_dealt += hit.damage as f64;
+    }
+
+    let kills = killed_events.read().count() as u64;
+    statistics.enemies_killed += kills;
+    run_stats.kills += kills;
+
+    for damaged in damaged_events.read() {
+        statistics.damage_taken += damaged.damage as f64;
+    }
+
+    statistics.shots_fired += shot_events.read().count() as u64;
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combat::apply_resistance,
+    components::{DamageType, Health},
+    configs::*,
+    enemy::{Enemy, EnemyType, LastDamageSource},
+    events::{DamageSource, StatusDamage},
+    state::GameState,
+    timescale::simulation_running,
+};
+
+pub struct StatusPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum StatusKind {
+    Burn,
+    Poison,
+    Slow,
+    Freeze,
+    Stun,
+}
+
+/// A single active effect. `magnitude` is damage per second for burn and poison,
+/// and the fraction of speed removed for slow; freeze and stun ignore it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub magnitude: f32,
+    pub remaining: f32,
+    pub stacks: u32,
+    pub tick_timer: f32,
+}
+
+#[derive(Component, Default, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+/// Chance for a hit to apply a status effect.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+pub struct StatusProc {
+    pub kind: StatusKind,
+    pub chance: f32,
+    pub magnitude: f32,
+    pub duration: f32,
+}
+
+#[derive(Component, Default, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct OnHitEffects {
+    pub procs: Vec<StatusProc>,
+}
+
+impl StatusKind {
+    pub fn deals_damage(&self) -> bool {
+        matches!(self, StatusKind::Burn | StatusKind::Poison)
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            StatusKind::Burn => DamageType::Fire,
+            StatusKind::Poison => DamageType::Poison,
+            StatusKind::Slow | StatusKind::Freeze => DamageType::Ice,
+            StatusKind::Stun => DamageType::Lightning,
+        }
+    }
+}
+
+impl StatusEffects {
+    /// Reapplying an effect keeps its highest magnitude and longest remaining duration;
+    /// poison additionally gains a stack, up to `POISON_MAX_STACKS`.
+    pub fn apply(&mut self, kind: StatusKind, magnitude: f32, duration: f32) {
+        let Some(effect) = self.effects.iter_mut().find(|effect| effect.kind == kind) else {
+            self.effects.push(StatusEffect {
+                kind,
+                magnitude,
+                remaining: duration,
+                stacks: 1,
+                tick_timer: 0.0,
+            });
+            return;
+        };
+
+        effect.remaining = effect.remaining.max(duration);
+        effect.magnitude = effect.magnitude.max(magnitude);
+        if kind == StatusKind::Poison {
+            effect.stacks = (effect.stacks + 1).min(POISON_MAX_STACKS);
+        }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Multiplier applied to movement speed; frozen or stunned enemies do not move.
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.has(StatusKind::Freeze) || self.has(StatusKind::Stun) {
+            return 0.0;
+        }
+
+        let slow = self
+            .effects
+            .iter()
+            .filter(|effect| effect.kind == StatusKind::Slow)
+            .map(|effect| effect.magnitude)
+            .fold(0.0, f32::max);
+        (1.0 - slow).clamp(0.0, 1.0)
+    }
+}
+
+impl StatusProc {
+    /// The proc a weapon of the given damage type comes with. Physical has none.
+    pub fn for_damage_type(damage_type: DamageType) -> Option<Self> {
+        let (kind, magnitude, duration) = match damage_type {
+            DamageType::Physical => return None,
+            DamageType::Fire => (StatusKind::Burn, BURN_DAMAGE_PER_SECOND, BURN_DURATION),
+            DamageType::Poison => (
+                StatusKind::Poison,
+                POISON_DAMAGE_PER_SECOND,
+                POISON_DURATION,
+            ),
+            DamageType::Ice => (StatusKind::Slow, SLOW_FRACTION, SLOW_DURATION),
+            DamageType::Lightning => (StatusKind::Stun, 0.0, STUN_DURATION),
+        };
+        Some(Self {
+            kind,
+            chance: WEAPON_PROC_CHANCE,
+            magnitude,
+            duration,
+        })
+    }
+}
+
+impl OnHitEffects {
+    /// These effects plus the proc that comes with a weapon's damage type.
+    pub fn with_damage_type(&self, damage_type: DamageType) -> Self {
+        let mut effects = self.clone();
+        effects
+            .procs
+            .extend(StatusProc::for_damage_type(damage_type));
+        effects
+    }
+
+    /// Rolls every proc once and applies the ones that succeed.
+    pub fn roll(&self, rng: &mut impl Rng, target: &mut StatusEffects) {
+        for proc in self.procs.iter() {
+            if rng.gen::<f32>() < proc.chance {
+                target.apply(proc.kind, proc.magnitude, proc.duration);
+            }
+        }
+    }
+}
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            tick_status_effects
+                .run_if(in_state(GameState::InGame))
+                .run_if(simulation_running),
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tick_status_effects(
+    time: Res<Time>,
+    mut enemy_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Health,
+            &mut StatusEffects,
+            &mut LastDamageSource,
+            &EnemyType,
+        ),
+        With<Enemy>,
+    >,
+    mut status_damage_events: EventWriter<StatusDamage>,
+) {
+    let delta = time.delta_seconds();
+
+    for (enemy, transform, mut health, mut status, mut last_source, enemy_type) in
+        enemy_query.iter_mut()
+    {
+        for effect in status.effects.iter_mut() {
+            effect.remaining -= delta;
+            if !effect.kind.deals_damage() {
+                continue;
+            }
+
+            effect.tick_timer += delta;
+            while effect.tick_timer >= STATUS_TICK_INTERVAL {
+                effect.tick_timer -= STATUS_TICK_INTERVAL;
+                let damage = effect.magnitude * effect.stacks as f32 * STATUS_TICK_INTERVAL;
+                let resistance = enemy_type.resistance(effect.kind.damage_type());
+                let damage = apply_resistance(damage, resistance);
+                health.value -= damage;
+                last_source.value = Some(DamageSource::Status(effect.kind));
+                status_damage_events.send(StatusDamage {
+                    enemy,
+                    position: transform.translation.truncate(),
+                    damage,
+                    kind: effect.kind,
+                });
+            }
+        }
+
+        status.effects.retain(|effect| effect.remaining > 0.0);
+    }
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeSystem;
+use bevy_egui::EguiContexts;
+
+use crate::bindings::{Action, ActionInput};
+use crate::configs::*;
+use crate::state::GameState;
+
+pub struct TimeScalePlugin;
+
+/// Simulation speed. Timers follow it through `Time<Virtual>`, and systems that move
+/// things a fixed distance per frame scale that distance by `factor`.
+#[derive(Resource)]
+pub struct TimeScale {
+    pub scale: f32,
+    /// Gameplay stops until unfrozen, apart from single steps.
+    pub frozen: bool,
+    step_requested: bool,
+    stepping: bool,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            frozen: false,
+            step_requested: false,
+            stepping: false,
+        }
+    }
+}
+
+impl TimeScale {
+    /// Sets the speed, freezing at zero.
+    pub fn set(&mut self, scale: f32) {
+        if scale <= 0.0 {
+            self.frozen = true;
+            return;
+        }
+        self.scale = scale.clamp(TIME_SCALE_MIN, TIME_SCALE_MAX);
+        self.frozen = false;
+    }
+
+    /// Freezes and advances exactly one tick on the next frame.
+    pub fn step(&mut self) {
+        self.frozen = true;
+        self.step_requested = true;
+    }
+
+    /// Whether gameplay systems run this frame.
+    pub fn running(&self) -> bool {
+        !self.frozen || self.stepping
+    }
+
+    /// How much of a normal frame's movement to apply this frame.
+    pub fn factor(&self) -> f32 {
+        if self.stepping {
+            1.0
+        } else if self.frozen {
+            0.0
+        } else {
+            self.scale
+        }
+    }
+}
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_systems(First, apply_time_scale.after(TimeSystem))
+            .add_systems(
+                Update,
+                handle_time_scale_keys.run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Last, finish_step);
+    }
+}
+
+/// Run condition for movement, spawn and firing systems.
+pub fn simulation_running(time_scale: Res<TimeScale>) -> bool {
+    time_scale.running()
+}
+
+fn handle_time_scale_keys(
+    mut contexts: EguiContexts,
+    input: ActionInput,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    // Typing into the debug console shouldn't change the speed
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    if input.just_pressed(Action::TimeSlower) {
+        let scale = time_scale.scale / TIME_SCALE_KEY_FACTOR;
+        time_scale.set(scale);
+    }
+    if input.just_pressed(Action::TimeFaster) {
+        let scale = time_scale.scale * TIME_SCALE_KEY_FACTOR;
+        time_scale.set(scale);
+    }
+    if input.just_pressed(Action::TimeReset) {
+        time_scale.set(1.0);
+    }
+    if input.just_pressed(Action::TimeFreeze) {
+        time_scale.frozen = !time_scale.frozen;
+    }
+    if input.just_pressed(Action::TimeStep) {
+        time_scale.step();
+    }
+}
+
+/// Keeps `Time<Virtual>` in line with the scale, and hands a step its one tick of time.
+fn apply_time_scale(
+    mut time_scale: ResMut<TimeScale>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time: ResMut<Time>,
+) {
+    if virtual_time.relative_speed() != time_scale.scale {
+        virtual_time.set_relative_speed(time_scale.scale);
+    }
+    if time_scale.frozen && !virtual_time.is_paused() {
+        virtual_time.pause();
+    } else if !time_scale.frozen && virtual_time.is_paused() {
+        virtual_time.unpause();
+    }
+
+    if time_scale.step_requested && time_scale.frozen {
+        // `Time` was already copied from the paused clock this frame, so advance both
+        let step = Duration::from_secs_f32(TIME_STEP_SECONDS);
+        virtual_time.advance_by(step);
+        time.advance_by(step);
+        time_scale.stepping = true;
+    }
+    time_scale.step_requested = false;
+}
+
+fn finish_step(mut time_scale: ResMut<TimeScale>) {
+    time_scale.stepping = false;
+}